@@ -0,0 +1,270 @@
+//! Safe writes of config.toml.
+//!
+//! New content is merged into the existing document so comments, key order and
+//! formatting written by hand survive a save from the settings page. The file
+//! is replaced by writing a temporary file in the same directory and renaming
+//! it over the original, so a crash leaves either the old or the new version,
+//! never a truncated one. Before each save the old version is copied to
+//! `backups/`, keeping the newest `MAX_BACKUPS`.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::AppConfig;
+
+const MAX_BACKUPS: usize = 10;
+
+/// A saved copy of config.toml
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigBackup {
+    /// File name, passed back to `restore_config_backup`
+    pub name: String,
+    pub created: DateTime<Local>,
+    pub size: u64,
+}
+
+/// Serialize `config`, keeping the layout of `existing` where possible
+pub fn render(existing: Option<&str>, config: &AppConfig) -> Result<String, String> {
+    let fresh =
+        toml::to_string_pretty(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    // Without a readable previous version there is no formatting to keep
+    let Some(mut document) = existing.and_then(|text| text.parse::<DocumentMut>().ok()) else {
+        return Ok(fresh);
+    };
+
+    let fresh: DocumentMut = fresh
+        .parse()
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    merge_table(document.as_table_mut(), fresh.as_table());
+
+    Ok(document.to_string())
+}
+
+/// Replace the file at `path` with `content`, backing up the old version
+/// unless `backup` is false
+pub fn save(path: &Path, content: &str, backup: bool) -> Result<(), String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("Invalid config path: {}", path.display()))?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+    if backup && path.exists() {
+        create_backup(path)?;
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = dir.join(format!(".{}.tmp", file_name));
+
+    let write_temp = || -> std::io::Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    };
+    if let Err(e) = write_temp() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Failed to write config file: {}", e));
+    }
+
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Failed to replace config file: {}", e)
+    })
+}
+
+/// Backups of the config file at `path`, newest first
+pub fn list_backups(path: &Path) -> Result<Vec<ConfigBackup>, String> {
+    let dir = backup_dir(path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = backup_prefix(path);
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read backup dir: {}", e))?;
+
+    let mut backups: Vec<ConfigBackup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(&prefix) || !name.ends_with(".toml") {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(ConfigBackup {
+                name,
+                created: metadata.modified().ok()?.into(),
+                size: metadata.len(),
+            })
+        })
+        .collect();
+
+    // Names embed the timestamp, so they sort chronologically
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Content of the backup called `name`
+pub fn read_backup(path: &Path, name: &str) -> Result<String, String> {
+    // Only names from `list_backups`, never arbitrary paths
+    if !list_backups(path)?.iter().any(|backup| backup.name == name) {
+        return Err(format!("Backup '{}' not found", name));
+    }
+
+    fs::read_to_string(backup_dir(path).join(name))
+        .map_err(|e| format!("Failed to read backup: {}", e))
+}
+
+fn create_backup(path: &Path) -> Result<PathBuf, String> {
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup dir: {}", e))?;
+
+    let stem = format!(
+        "{}{}",
+        backup_prefix(path),
+        Local::now().format("%Y%m%d-%H%M%S-%6f")
+    );
+    let mut backup_path = dir.join(format!("{}.toml", stem));
+    for n in 1.. {
+        if !backup_path.exists() {
+            break;
+        }
+        backup_path = dir.join(format!("{}-{}.toml", stem, n));
+    }
+    fs::copy(path, &backup_path).map_err(|e| format!("Failed to back up config: {}", e))?;
+
+    // Pruning is best effort, a leftover backup does no harm
+    if let Ok(backups) = list_backups(path) {
+        for backup in backups.iter().skip(MAX_BACKUPS) {
+            let _ = fs::remove_file(dir.join(&backup.name));
+        }
+    }
+
+    Ok(backup_path)
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new(".")).join("backups")
+}
+
+/// "config-" for config.toml, so backups of different files do not mix
+fn backup_prefix(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}-", stem)
+}
+
+/// Make `target` hold the data of `source` while keeping its comments,
+/// ordering and formatting for everything that did not change
+fn merge_table(target: &mut Table, source: &Table) {
+    target.retain(|key, _| source.contains_key(key));
+
+    for (key, new) in source.iter() {
+        let Some(old) = target.get_mut(key) else {
+            target.insert(key, new.clone());
+            continue;
+        };
+
+        match (old, new) {
+            (Item::Table(old), Item::Table(new)) => merge_table(old, new),
+            // Hand-written `retry = { ... }` stays inline
+            (Item::Value(old), Item::Table(new)) if old.is_inline_table() => {
+                replace_value(old, &Value::InlineTable(new.clone().into_inline_table()));
+            }
+            (Item::Value(old), Item::Value(new)) => replace_value(old, new),
+            (old, new) => *old = new.clone(),
+        }
+    }
+}
+
+/// Overwrite `old` unless it already holds the same data, keeping the
+/// comments around it
+fn replace_value(old: &mut Value, new: &Value) {
+    if same_value(old, new) {
+        return;
+    }
+    let decor = old.decor().clone();
+    *old = new.clone();
+    *old.decor_mut() = decor;
+}
+
+/// Compare data only, ignoring formatting such as quote style
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        (Value::InlineTable(a), Value::InlineTable(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same_value(a, b)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXISTING: &str = r#"# Lab config, edited by hand
+version = 1
+default = "openai"
+
+[providers.openai]
+type = "openai"
+model = "gpt-4o-mini" # cheapest that works
+base_url = "https://api.openai.com/v1"
+api_key = "env:OPENAI_API_KEY"
+retry = { max_attempts = 5, initial_backoff_ms = 500, max_backoff_ms = 8000 }
+
+# Old proxy, to be removed
+[providers.old]
+type = "openai"
+model = "gpt-3.5-turbo"
+base_url = "http://localhost:8080/v1"
+api_key = ""
+"#;
+
+    #[test]
+    fn render_keeps_layout_of_existing_document() {
+        let mut config: AppConfig = toml::from_str(EXISTING).unwrap();
+        config.providers.remove("old");
+        let openai = config.providers.get_mut("openai").unwrap();
+        openai.model = "gpt-4o".to_string();
+        openai.retry.as_mut().unwrap().max_attempts = 3;
+
+        let rendered = render(Some(EXISTING), &config).unwrap();
+
+        assert_eq!(
+            rendered,
+            r#"# Lab config, edited by hand
+version = 1
+default = "openai"
+
+[providers.openai]
+type = "openai"
+model = "gpt-4o" # cheapest that works
+base_url = "https://api.openai.com/v1"
+api_key = "env:OPENAI_API_KEY"
+retry = { max_attempts = 3, initial_backoff_ms = 500, max_backoff_ms = 8000 }
+"#
+        );
+    }
+
+    #[test]
+    fn render_without_existing_document_serializes_fresh() {
+        let config: AppConfig = toml::from_str(EXISTING).unwrap();
+        let rendered = render(None, &config).unwrap();
+
+        assert!(!rendered.contains("# Lab config"));
+        assert!(rendered.contains("[providers.old]"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Reload config.toml when it is edited outside the app.
+//!
+//! The parent directory is watched rather than the file itself because many
+//! editors save by writing a temporary file and renaming it over the original,
+//! which ends a watch on the old inode.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Quiet period after the last file event before reloading, so a save that
+/// touches the file several times is handled once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub struct ConfigWatcher {
+    /// Content last written or reloaded by the app, to ignore our own saves
+    known: Arc<Mutex<Option<String>>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, calling `on_change` from a background thread whenever its
+    /// content changes on disk
+    pub fn start<F>(path: PathBuf, on_change: F) -> Result<Self, String>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let dir = path
+            .parent()
+            .ok_or_else(|| format!("Invalid config path: {}", path.display()))?
+            .to_path_buf();
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(tx)
+            .map_err(|e| format!("Failed to create config watcher: {}", e))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+        let known = Arc::new(Mutex::new(fs::read_to_string(&path).ok()));
+        let thread_known = known.clone();
+
+        let touches_config = move |event: &notify::Result<notify::Event>| {
+            event.as_ref().is_ok_and(|event| {
+                !event.kind.is_access()
+                    && event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == file_name.as_deref())
+            })
+        };
+
+        // Ends when the watcher, and with it the sender, is dropped
+        thread::spawn(move || {
+            while let Ok(event) = rx.recv() {
+                if !touches_config(&event) {
+                    continue;
+                }
+                while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+                // A missing file is usually the middle of a rename; the
+                // create event that follows triggers the reload
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+
+                {
+                    let mut known = thread_known.lock().unwrap();
+                    if known.as_deref() == Some(content.as_str()) {
+                        continue;
+                    }
+                    *known = Some(content);
+                }
+
+                on_change();
+            }
+        });
+
+        Ok(Self {
+            known,
+            _watcher: watcher,
+        })
+    }
+
+    /// Record content the app has written itself so it is not reported back
+    pub fn remember(&self, content: &str) {
+        *self.known.lock().unwrap() = Some(content.to_string());
+    }
+}
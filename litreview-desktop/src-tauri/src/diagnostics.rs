@@ -0,0 +1,383 @@
+//! Step-by-step connection checks behind `test_llm_connection`.
+//!
+//! DNS, TCP and TLS are probed on a connection of their own so each step can
+//! be timed; reqwest does not expose those phases. The provider is then called
+//! through the shared client, first with a minimal request and then with a
+//! short streamed one, and any failure is classified so the settings page can
+//! say what to fix rather than show a raw HTTP body.
+
+use futures::StreamExt;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio_native_tls::native_tls;
+
+use crate::error::{classify_request_error, AppError, ErrorKind};
+use crate::network::{self, NetworkConfig};
+use crate::providers::{LlmProvider, OpenAiApi};
+use crate::{GenerationParams, StreamDecoder, StreamRequestConfig};
+
+/// Limit for each of the DNS, connect and TLS probes
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Limit for each of the two provider requests
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Check that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticStep {
+    Url,
+    Dns,
+    Connect,
+    Tls,
+    Request,
+    Stream,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticError {
+    pub step: DiagnosticStep,
+    #[serde(flatten)]
+    pub error: AppError,
+}
+
+/// Result of `test_llm_connection`; timings are in milliseconds and unset for
+/// steps that were skipped or never reached
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiagnosticReport {
+    pub ok: bool,
+    /// Proxy the requests go through; DNS and connect timings are then those
+    /// of the proxy, and TLS is not probed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    pub dns_ms: Option<u64>,
+    /// Addresses the host resolved to
+    pub addresses: Vec<String>,
+    pub connect_ms: Option<u64>,
+    pub tls_ms: Option<u64>,
+    /// HTTP status of the minimal request
+    pub status: Option<u16>,
+    /// Round trip of the minimal request
+    pub response_ms: Option<u64>,
+    /// Whether the streamed request delivered text incrementally
+    pub streaming: Option<bool>,
+    /// From sending the streamed request to its first text
+    pub first_token_ms: Option<u64>,
+    pub stream_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<DiagnosticError>,
+    /// Problems that did not stop the checks
+    pub warnings: Vec<String>,
+}
+
+impl DiagnosticReport {
+    fn fail(mut self, step: DiagnosticStep, error: AppError) -> Self {
+        self.ok = false;
+        self.error = Some(DiagnosticError { step, error });
+        self
+    }
+}
+
+/// Run every check against `config`, stopping at the first failure
+pub async fn run(
+    client: &Client,
+    network: &NetworkConfig,
+    provider: Arc<dyn LlmProvider>,
+    config: &StreamRequestConfig,
+) -> DiagnosticReport {
+    let mut report = DiagnosticReport::default();
+
+    let url = match Url::parse(&config.base_url) {
+        Ok(url) if url.host_str().is_some() => url,
+        Ok(_) => {
+            return report.fail(
+                DiagnosticStep::Url,
+                AppError::new(ErrorKind::BadRequest, "invalid_url", "Base URL has no host"),
+            )
+        }
+        Err(e) => {
+            return report.fail(
+                DiagnosticStep::Url,
+                AppError::new(
+                    ErrorKind::BadRequest,
+                    "invalid_url",
+                    format!("Invalid base URL: {}", e),
+                ),
+            )
+        }
+    };
+
+    let proxy = proxy_for(&url, network);
+    report.proxy = proxy.as_ref().map(|proxy| proxy.to_string());
+    let probe_url = proxy.as_ref().unwrap_or(&url);
+    let unreachable = if proxy.is_some() {
+        ErrorKind::Proxy
+    } else {
+        ErrorKind::Network
+    };
+
+    let host = probe_url.host_str().unwrap_or_default().to_string();
+    let port = probe_url.port_or_known_default().unwrap_or(443);
+
+    // DNS
+    let started = Instant::now();
+    let addresses = match timed(lookup_host((host.as_str(), port))).await {
+        Ok(addresses) => addresses.collect::<Vec<_>>(),
+        Err(e) => {
+            return report.fail(
+                DiagnosticStep::Dns,
+                AppError::new(
+                    unreachable,
+                    "dns_failed",
+                    format!("Could not resolve {}", host),
+                )
+                .with_raw(e),
+            )
+        }
+    };
+    report.dns_ms = Some(elapsed_ms(started));
+    report.addresses = addresses.iter().map(|addr| addr.ip().to_string()).collect();
+
+    // TCP
+    let Some(address) = addresses.first() else {
+        return report.fail(
+            DiagnosticStep::Dns,
+            AppError::new(
+                unreachable,
+                "dns_failed",
+                format!("{} has no addresses", host),
+            ),
+        );
+    };
+    let started = Instant::now();
+    let stream = match timed(TcpStream::connect(address)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            return report.fail(
+                DiagnosticStep::Connect,
+                AppError::new(
+                    unreachable,
+                    "connect_failed",
+                    format!("Could not connect to {}:{}", host, port),
+                )
+                .with_raw(e),
+            )
+        }
+    };
+    report.connect_ms = Some(elapsed_ms(started));
+
+    // TLS, only meaningful on a direct connection
+    if proxy.is_none() && url.scheme() == "https" {
+        let started = Instant::now();
+        if let Err(e) = tls_handshake(stream, &host, network).await {
+            return report.fail(
+                DiagnosticStep::Tls,
+                AppError::new(ErrorKind::Tls, "tls_failed", "TLS handshake failed").with_raw(e),
+            );
+        }
+        report.tls_ms = Some(elapsed_ms(started));
+    } else {
+        drop(stream);
+    }
+
+    // One-token request: credentials and model
+    let started = Instant::now();
+    let response = match provider
+        .build_health_check(client, config)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            return report.fail(DiagnosticStep::Request, request_error(&e, proxy.is_some()));
+        }
+    };
+    report.response_ms = Some(elapsed_ms(started));
+    let status = response.status();
+    report.status = Some(status.as_u16());
+
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return report.fail(DiagnosticStep::Request, AppError::http(status, body));
+    }
+
+    // Short streamed request: incremental delivery and latency
+    check_streaming(report, client, provider, config, proxy.is_some()).await
+}
+
+async fn check_streaming(
+    mut report: DiagnosticReport,
+    client: &Client,
+    provider: Arc<dyn LlmProvider>,
+    config: &StreamRequestConfig,
+    proxied: bool,
+) -> DiagnosticReport {
+    // Reasoning models behind the Responses API reject `temperature`
+    let temperature = match config.openai_api {
+        Some(OpenAiApi::Responses) => None,
+        _ => Some(0.0),
+    };
+    let config = StreamRequestConfig {
+        prompt: "Reply with the single word: OK".to_string(),
+        system_prompt: None,
+        messages: Vec::new(),
+        params: GenerationParams {
+            temperature,
+            max_tokens: Some(16),
+            ..Default::default()
+        },
+        // A thinking budget would exceed the 16 token limit
+        thinking_budget: None,
+        ..config.clone()
+    };
+
+    let started = Instant::now();
+    let response = match provider
+        .build_stream_request(client, &config)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return report.fail(DiagnosticStep::Stream, request_error(&e, proxied)),
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return report.fail(DiagnosticStep::Stream, AppError::http(status, body));
+    }
+
+    let Some(mut decoder) = StreamDecoder::for_response(&response) else {
+        // Usable, but text arrives only once generation has finished
+        let _ = response.bytes().await;
+        report.stream_ms = Some(elapsed_ms(started));
+        report.streaming = Some(false);
+        report
+            .warnings
+            .push("The server ignored streaming and returned a complete response".to_string());
+        report.ok = true;
+        return report;
+    };
+
+    let mut stream = response.bytes_stream();
+    let mut finished = false;
+
+    while !finished {
+        let events = match stream.next().await {
+            Some(Ok(chunk)) => decoder.feed(&chunk),
+            Some(Err(e)) => {
+                return report.fail(DiagnosticStep::Stream, request_error(&e, proxied));
+            }
+            None => {
+                finished = true;
+                decoder.finish()
+            }
+        };
+
+        for data in events {
+            let parsed = provider.parse_stream_data(&data);
+            let produced = !parsed.deltas.is_empty() || !parsed.reasoning.is_empty();
+            if produced && report.first_token_ms.is_none() {
+                report.first_token_ms = Some(elapsed_ms(started));
+            }
+            if let Some(error) = parsed.error {
+                return report.fail(DiagnosticStep::Stream, error);
+            }
+            finished |= parsed.done;
+        }
+    }
+
+    report.stream_ms = Some(elapsed_ms(started));
+    report.streaming = Some(report.first_token_ms.is_some());
+    if report.first_token_ms.is_none() {
+        report
+            .warnings
+            .push("The stream ended without any text".to_string());
+    }
+    report.ok = true;
+    report
+}
+
+/// `AppError::request`, blaming the proxy for connection failures when
+/// there is one
+fn request_error(error: &reqwest::Error, proxied: bool) -> AppError {
+    AppError {
+        kind: classify_request_error(error, proxied),
+        ..AppError::request(error)
+    }
+}
+
+/// The proxy reqwest will use for `url`: the configured one unless `no_proxy`
+/// exempts the host, otherwise the usual environment variables
+fn proxy_for(url: &Url, network: &NetworkConfig) -> Option<Url> {
+    let host = url.host_str().unwrap_or_default();
+
+    if let Some(proxy) = network.proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        let exempt = network
+            .no_proxy
+            .as_deref()
+            .is_some_and(|list| matches_no_proxy(list, host));
+        return if exempt { None } else { Url::parse(proxy).ok() };
+    }
+
+    let env = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+    };
+    if env(&["NO_PROXY", "no_proxy"]).is_some_and(|list| matches_no_proxy(&list, host)) {
+        return None;
+    }
+    let proxy = match url.scheme() {
+        "https" => env(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]),
+        _ => env(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]),
+    }?;
+    Url::parse(&proxy).ok()
+}
+
+/// Whether a comma-separated no-proxy list covers `host`
+fn matches_no_proxy(list: &str, host: &str) -> bool {
+    list.split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| entry == "*" || host == entry || host.ends_with(&format!(".{}", entry)))
+}
+
+/// Handshake on `stream` with the same trust settings as the shared client
+async fn tls_handshake(
+    stream: TcpStream,
+    host: &str,
+    network: &NetworkConfig,
+) -> Result<(), String> {
+    let mut builder = native_tls::TlsConnector::builder();
+
+    for certificate in network::load_ca_bundle(network, native_tls::Certificate::from_pem)? {
+        builder.add_root_certificate(certificate);
+    }
+    if network.insecure {
+        builder.danger_accept_invalid_certs(true);
+    }
+
+    let connector =
+        tokio_native_tls::TlsConnector::from(builder.build().map_err(|e| e.to_string())?);
+    timed(connector.connect(host, stream)).await.map(|_| ())
+}
+
+/// Await `future` for at most `PROBE_TIMEOUT`
+async fn timed<T, E: std::fmt::Display>(
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(PROBE_TIMEOUT, future).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("Timed out after {}s", PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
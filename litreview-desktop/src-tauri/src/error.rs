@@ -0,0 +1,283 @@
+//! Error type returned by commands and carried in stream events.
+//!
+//! Internal helpers still return `Result<_, String>`; commands attach a kind
+//! where the error leaves the backend. The frontend picks the text to show
+//! from `kind`, so `message` is English detail for logs and bug reports.
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::retry;
+
+/// Longest response body kept in `AppError.raw`
+const MAX_RAW_CHARS: usize = 2000;
+
+/// What went wrong, as far as it can be told from the response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Missing, invalid or revoked API key, or no access to the model
+    Auth,
+    ModelNotFound,
+    /// The endpoint does not exist, usually a wrong base URL
+    NotFound,
+    /// Rate limit, exhausted quota or billing problem
+    Quota,
+    /// DNS failure, refused or dropped connection
+    Network,
+    Timeout,
+    /// The proxy is unreachable or rejected the request
+    Proxy,
+    /// Certificate or handshake failure
+    Tls,
+    /// The provider rejected the request itself
+    BadRequest,
+    Server,
+    /// The provider answered with something that could not be parsed
+    InvalidResponse,
+    /// config.toml is unreadable, invalid or lacks what was asked for
+    Config,
+    /// A command argument was rejected
+    InvalidInput,
+    /// Reading or writing a file or the secret store failed
+    Storage,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    /// The provider's own error code when it sent one (e.g.
+    /// "insufficient_quota"), otherwise a fixed code such as "http_404"
+    pub code: String,
+    pub message: String,
+    /// Provider name the failed request was made for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// Trying again unchanged may succeed
+    #[serde(default)]
+    pub retryable: bool,
+    /// Response body or underlying error chain, truncated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            code: code.into(),
+            message: message.into(),
+            provider: None,
+            http_status: None,
+            retryable: matches!(kind, ErrorKind::Network | ErrorKind::Timeout),
+            raw: None,
+        }
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Config, "config", message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, "invalid_input", message)
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Storage, "storage", message)
+    }
+
+    pub fn invalid_response(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidResponse, "invalid_response", message)
+    }
+
+    /// A non-success HTTP response
+    pub fn http(status: StatusCode, body: String) -> Self {
+        let json = serde_json::from_str::<serde_json::Value>(&body).ok();
+        let detail = json.as_ref().and_then(provider_message);
+        let code = json
+            .as_ref()
+            .and_then(provider_code)
+            .unwrap_or_else(|| format!("http_{}", status.as_u16()));
+
+        Self {
+            kind: classify_status(status, &body),
+            code,
+            message: match detail {
+                Some(detail) => format!("HTTP {}: {}", status, detail),
+                None => format!("HTTP {}", status),
+            },
+            provider: None,
+            http_status: Some(status.as_u16()),
+            retryable: retry::is_retryable_status(status),
+            raw: Some(body).filter(|body| !body.is_empty()).map(truncate),
+        }
+    }
+
+    /// An error reported inside an otherwise successful stream
+    ///
+    /// `error` is the `error` value of the payload: an object with a message
+    /// and code, type or status (Claude, Gemini, OpenAI-compatible servers) or
+    /// a bare string (Ollama).
+    pub fn stream(error: &serde_json::Value) -> Self {
+        let message =
+            error_message(error).unwrap_or_else(|| "The stream reported an error".to_string());
+        let code = error_code(error).unwrap_or_else(|| "stream_error".to_string());
+
+        // Claude sends `overloaded_error` mid-stream where it would answer 529,
+        // Gemini `RESOURCE_EXHAUSTED` where it would answer 429
+        let detail = format!("{} {}", code, message).to_lowercase();
+        let mentions = |words: &[&str]| words.iter().any(|word| detail.contains(word));
+        let kind = if mentions(&["rate_limit", "resource_exhausted"]) {
+            ErrorKind::Quota
+        } else {
+            ErrorKind::Server
+        };
+
+        Self {
+            retryable: mentions(&[
+                "overloaded",
+                "rate_limit",
+                "resource_exhausted",
+                "unavailable",
+                "server_error",
+                "api_error",
+            ]),
+            raw: Some(truncate(error.to_string())),
+            ..Self::new(kind, code, message)
+        }
+    }
+
+    /// A request that got no (complete) response
+    pub fn request(error: &reqwest::Error) -> Self {
+        let kind = classify_request_error(error, false);
+        Self {
+            retryable: retry::is_transient_error(error),
+            raw: Some(error_chain(error)),
+            ..Self::new(kind, "request_failed", format!("Network error: {}", error))
+        }
+    }
+
+    pub fn with_provider(mut self, provider: Option<&str>) -> Self {
+        self.provider = provider.map(str::to_string);
+        self
+    }
+
+    pub fn with_raw(mut self, raw: impl Into<String>) -> Self {
+        self.raw = Some(truncate(raw.into()));
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Classify a non-success response by status and the provider's error body
+pub fn classify_status(status: StatusCode, body: &str) -> ErrorKind {
+    let body = body.to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| body.contains(word));
+
+    // OpenAI reports an empty balance as 429, Gemini as RESOURCE_EXHAUSTED
+    if mentions(&["insufficient_quota", "quota", "billing", "credit balance"]) {
+        return ErrorKind::Quota;
+    }
+
+    let model_missing = mentions(&["model"])
+        && mentions(&[
+            "not found",
+            "not_found",
+            "does not exist",
+            "invalid model",
+            "unknown model",
+        ]);
+
+    match status.as_u16() {
+        401 | 403 => ErrorKind::Auth,
+        402 | 429 => ErrorKind::Quota,
+        // Azure OpenAI reports a wrong deployment name as DeploymentNotFound
+        404 if mentions(&["model", "deployment"]) => ErrorKind::ModelNotFound,
+        404 => ErrorKind::NotFound,
+        407 => ErrorKind::Proxy,
+        // Gemini answers an invalid key with 400 API_KEY_INVALID
+        400 | 422 if mentions(&["api key", "api_key", "apikey", "unauthorized"]) => ErrorKind::Auth,
+        400 | 422 if model_missing => ErrorKind::ModelNotFound,
+        408 => ErrorKind::Timeout,
+        400..=499 => ErrorKind::BadRequest,
+        500..=599 => ErrorKind::Server,
+        _ => ErrorKind::Unknown,
+    }
+}
+
+/// Classify a request that got no response
+pub fn classify_request_error(error: &reqwest::Error, proxied: bool) -> ErrorKind {
+    if error.is_timeout() {
+        return ErrorKind::Timeout;
+    }
+
+    let chain = error_chain(error).to_lowercase();
+    if ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|word| chain.contains(word))
+    {
+        ErrorKind::Tls
+    } else if chain.contains("proxy") || (proxied && error.is_connect()) {
+        ErrorKind::Proxy
+    } else {
+        ErrorKind::Network
+    }
+}
+
+/// An error with its sources, which is where reqwest keeps the useful part
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// `error.message` (OpenAI, Claude, Gemini) or a bare string `error`
+fn provider_message(json: &serde_json::Value) -> Option<String> {
+    error_message(json.get("error")?)
+}
+
+/// `error.code` (OpenAI), `error.type` (Claude) or `error.status` (Gemini)
+fn provider_code(json: &serde_json::Value) -> Option<String> {
+    error_code(json.get("error")?)
+}
+
+fn error_message(error: &serde_json::Value) -> Option<String> {
+    error
+        .get("message")
+        .and_then(|message| message.as_str())
+        .or_else(|| error.as_str())
+        .map(str::to_string)
+}
+
+fn error_code(error: &serde_json::Value) -> Option<String> {
+    ["code", "type", "status"].iter().find_map(|key| {
+        error
+            .get(*key)
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    })
+}
+
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_RAW_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
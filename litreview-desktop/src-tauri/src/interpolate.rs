@@ -0,0 +1,133 @@
+//! Environment variable placeholders in config.toml values.
+//!
+//! A value is either `env:NAME`, replaced entirely by the variable, or a string
+//! containing `${NAME}` placeholders, e.g. `"${LAB_PROXY_HOST}/v1"`. Text that
+//! only looks similar (`$NAME`, an unclosed `${`) is kept literally.
+
+use std::env;
+
+const ENV_PREFIX: &str = "env:";
+
+/// Whether `value` refers to the environment at all
+pub fn has_placeholders(value: &str) -> bool {
+    env_reference(value).is_some() || placeholders(value).next().is_some()
+}
+
+/// Resolve all placeholders in `value`
+///
+/// Fails on the first variable that is unset or not valid unicode.
+pub fn expand(value: &str) -> Result<String, String> {
+    if let Some(name) = env_reference(value) {
+        return lookup(name);
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = 0;
+    for (start, end, name) in placeholders(value) {
+        expanded.push_str(&value[rest..start]);
+        expanded.push_str(&lookup(name)?);
+        rest = end;
+    }
+    expanded.push_str(&value[rest..]);
+
+    Ok(expanded)
+}
+
+fn lookup(name: &str) -> Result<String, String> {
+    env::var(name).map_err(|e| format!("Environment variable '{}': {}", name, e))
+}
+
+fn env_reference(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(ENV_PREFIX)
+        .map(str::trim)
+        .filter(|name| is_var_name(name))
+}
+
+/// `(start, end, name)` of every `${NAME}` in `value`
+fn placeholders(value: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        while let Some(found) = value[offset..].find("${") {
+            let start = offset + found;
+            let Some(close) = value[start + 2..].find('}') else {
+                offset = value.len();
+                return None;
+            };
+            let end = start + 2 + close + 1;
+            let name = &value[start + 2..end - 1];
+            if is_var_name(name) {
+                offset = end;
+                return Some((start, end, name));
+            }
+            offset = start + 2;
+        }
+        None
+    })
+}
+
+fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests run in parallel, so each one sets its own variables
+
+    #[test]
+    fn expands_env_reference_and_placeholders() {
+        env::set_var("INTERPOLATE_TEST_KEY", "sk-test");
+        env::set_var("INTERPOLATE_TEST_HOST", "http://lab");
+        assert_eq!(expand("env:INTERPOLATE_TEST_KEY").unwrap(), "sk-test");
+        assert_eq!(expand("env: INTERPOLATE_TEST_KEY ").unwrap(), "sk-test");
+        assert_eq!(
+            expand("${INTERPOLATE_TEST_HOST}/v1").unwrap(),
+            "http://lab/v1"
+        );
+    }
+
+    #[test]
+    fn expands_adjacent_placeholders() {
+        env::set_var("INTERPOLATE_TEST_A", "a");
+        env::set_var("INTERPOLATE_TEST_B", "b");
+        assert_eq!(
+            expand("${INTERPOLATE_TEST_A}${INTERPOLATE_TEST_B}-${INTERPOLATE_TEST_A}").unwrap(),
+            "ab-a"
+        );
+    }
+
+    #[test]
+    fn keeps_lookalikes_literally() {
+        for value in [
+            "${UNCLOSED",
+            "${1X}",
+            "$NAME",
+            "${}",
+            "prefix env:NAME",
+            "env:NAME/v1",
+        ] {
+            assert!(!has_placeholders(value), "{}", value);
+            assert_eq!(expand(value).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn skips_invalid_name_before_valid_one() {
+        env::set_var("INTERPOLATE_TEST_C", "c");
+        assert!(has_placeholders("${1X}${INTERPOLATE_TEST_C}"));
+        assert_eq!(expand("${1X}${INTERPOLATE_TEST_C}").unwrap(), "${1X}c");
+    }
+
+    #[test]
+    fn fails_on_unset_variable() {
+        let error = expand("${INTERPOLATE_TEST_UNSET}").unwrap_err();
+        assert!(error.contains("INTERPOLATE_TEST_UNSET"));
+        assert!(expand("env:INTERPOLATE_TEST_UNSET").is_err());
+    }
+}
@@ -1,10 +1,44 @@
+mod config_file;
+mod config_watcher;
+mod diagnostics;
+mod error;
+mod interpolate;
+mod ndjson;
+mod network;
+mod portable;
+mod providers;
+mod retry;
+mod schema;
+mod secrets;
+mod sse;
+mod usage;
+
+use config_file::ConfigBackup;
+use config_watcher::ConfigWatcher;
+use diagnostics::DiagnosticReport;
+use error::{AppError, ErrorKind};
 use futures::StreamExt;
+use ndjson::NdjsonDecoder;
+use network::{HttpClient, NetworkConfig};
+use portable::{ConflictStrategy, ImportSummary};
+use providers::{
+    FinishReason, LlmProvider, ModelInfo, OllamaOptions, OpenAiApi, ParsedChunk, ProviderRegistry,
+    StreamSummary, TokenUsage,
+};
 use reqwest::Client;
+use retry::RetryPolicy;
+use schema::ValidationReport;
+use secrets::SecretStore;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sse::SseDecoder;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use tauri::{AppHandle, Emitter, Manager};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use usage::{LedgerEntry, ModelPrice, UsageBucket, UsageGrouping, UsageLedger};
 use uuid::Uuid;
 
 // ============================================================================
@@ -14,6 +48,10 @@ use uuid::Uuid;
 /// Configuration for streaming LLM requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct StreamRequestConfig {
+    /// Provider name (key in `AppConfig.providers`), recorded in the usage ledger
+    provider: Option<String>,
+    /// Frontend feature that started the stream, e.g. "review" or "polish"
+    feature: Option<String>,
     provider_type: String,
     base_url: String,
     api_key: String,
@@ -21,18 +59,285 @@ struct StreamRequestConfig {
     prompt: String,
     api_version: Option<String>,
     system_prompt: Option<String>,
+    /// Earlier turns of the conversation; `prompt` is sent as the next user turn
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+    #[serde(flatten)]
+    params: GenerationParams,
+    #[serde(default)]
+    openai_api: Option<OpenAiApi>,
+    /// Azure OpenAI deployment name
+    #[serde(default)]
+    deployment: Option<String>,
+    #[serde(default)]
+    ollama: Option<OllamaOptions>,
+    /// Extended thinking budget in tokens (Claude and Gemini)
+    #[serde(default)]
+    thinking_budget: Option<u32>,
 }
 
-/// Configuration for provider-specific streaming
-#[derive(Debug, Clone)]
-struct ProviderStreamConfig<'a> {
-    stream_id: &'a str,
-    base_url: &'a str,
-    api_key: &'a str,
-    model: &'a str,
-    prompt: &'a str,
-    api_version: Option<&'a str>,
-    system_prompt: Option<&'a str>,
+impl StreamRequestConfig {
+    /// The full conversation: history followed by `prompt` (if non-empty)
+    ///
+    /// System messages are excluded, see [`Self::system_text`].
+    fn conversation(&self) -> Vec<ChatMessage> {
+        let mut turns: Vec<ChatMessage> = self
+            .messages
+            .iter()
+            .filter(|m| m.role != ChatRole::System)
+            .cloned()
+            .collect();
+
+        if !self.prompt.is_empty() {
+            turns.push(ChatMessage {
+                role: ChatRole::User,
+                content: self.prompt.clone(),
+            });
+        }
+
+        turns
+    }
+
+    /// This request redirected to another configured provider
+    ///
+    /// The provider's own sampling defaults replace the request's, which the
+    /// frontend filled in from the original provider.
+    fn for_provider(&self, name: &str, provider: &ProviderConfig) -> Self {
+        Self {
+            provider: Some(name.to_string()),
+            provider_type: provider.provider_type.clone(),
+            base_url: provider.base_url.clone(),
+            api_key: provider.api_key.clone(),
+            model: provider.model.clone(),
+            api_version: provider.api_version.clone(),
+            params: provider.params.clone(),
+            openai_api: provider.openai_api,
+            deployment: provider.deployment.clone(),
+            ollama: provider.ollama.clone(),
+            thinking_budget: provider.thinking_budget,
+            ..self.clone()
+        }
+    }
+
+    /// A request to `provider` without a prompt, for calls other than generation
+    fn for_request(name: Option<&str>, provider: &ProviderConfig) -> Self {
+        Self {
+            provider: name.map(str::to_string),
+            feature: None,
+            provider_type: provider.provider_type.clone(),
+            base_url: provider.base_url.clone(),
+            api_key: provider.api_key.clone(),
+            model: provider.model.clone(),
+            prompt: String::new(),
+            api_version: provider.api_version.clone(),
+            system_prompt: None,
+            messages: Vec::new(),
+            params: GenerationParams::default(),
+            openai_api: provider.openai_api,
+            deployment: provider.deployment.clone(),
+            ollama: provider.ollama.clone(),
+            thinking_budget: provider.thinking_budget,
+        }
+    }
+
+    /// `system_prompt` combined with any system messages from the history
+    fn system_text(&self) -> Option<String> {
+        let parts: Vec<&str> = self
+            .system_prompt
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.messages
+                    .iter()
+                    .filter(|m| m.role == ChatRole::System)
+                    .map(|m| m.content.as_str()),
+            )
+            .filter(|text| !text.is_empty())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n\n"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// Optional sampling parameters
+///
+/// Unset fields are omitted from the request so the provider's own default
+/// applies, except where a provider needs a value (e.g. Claude `max_tokens`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+impl GenerationParams {
+    /// `self` with every field set in `overrides` replaced
+    fn with_overrides(&self, overrides: &GenerationParams) -> Self {
+        Self {
+            temperature: overrides.temperature.or(self.temperature),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            top_p: overrides.top_p.or(self.top_p),
+            stop_sequences: overrides
+                .stop_sequences
+                .clone()
+                .or_else(|| self.stop_sequences.clone()),
+        }
+    }
+}
+
+/// A single turn of a multi-turn conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChatMessage {
+    role: ChatRole,
+    content: String,
+}
+
+/// A spawned streaming task
+struct ActiveStream {
+    task: JoinHandle<()>,
+    /// The request in flight, so a cancelled stream can still be recorded
+    attempt: Option<StreamAttempt>,
+}
+
+/// One request of a stream and the usage reported for it so far
+struct StreamAttempt {
+    config: StreamRequestConfig,
+    started: Instant,
+    summary: StreamSummary,
+}
+
+/// Spawned streaming tasks keyed by stream_id, so they can be cancelled
+#[derive(Default)]
+struct ActiveStreams {
+    streams: Mutex<HashMap<String, ActiveStream>>,
+}
+
+impl ActiveStreams {
+    fn remove(&self, stream_id: &str) -> Option<ActiveStream> {
+        self.streams.lock().unwrap().remove(stream_id)
+    }
+
+    /// Start tracking a request to `config`, replacing any earlier attempt
+    fn begin_attempt(&self, stream_id: &str, config: &StreamRequestConfig) {
+        if let Some(stream) = self.streams.lock().unwrap().get_mut(stream_id) {
+            stream.attempt = Some(StreamAttempt {
+                config: config.clone(),
+                started: Instant::now(),
+                summary: StreamSummary::default(),
+            });
+        }
+    }
+
+    fn record(&self, stream_id: &str, chunk: &ParsedChunk) {
+        let mut streams = self.streams.lock().unwrap();
+        if let Some(attempt) = streams
+            .get_mut(stream_id)
+            .and_then(|stream| stream.attempt.as_mut())
+        {
+            attempt.summary.record(chunk);
+        }
+    }
+
+    /// Stop tracking the current attempt once it has completed
+    /// Returns false if the stream was cancelled, which records it instead
+    fn end_attempt(&self, stream_id: &str) -> bool {
+        self.streams
+            .lock()
+            .unwrap()
+            .get_mut(stream_id)
+            .is_some_and(|stream| stream.attempt.take().is_some())
+    }
+}
+
+/// The config as last loaded or saved
+///
+/// Streams read it from here rather than from disk, so starting one neither
+/// touches the secret store nor rewrites config.toml.
+#[derive(Default)]
+struct LoadedConfig {
+    config: Mutex<Option<AppConfig>>,
+}
+
+impl LoadedConfig {
+    fn get(&self) -> Option<AppConfig> {
+        self.config.lock().unwrap().clone()
+    }
+
+    fn set(&self, config: AppConfig) {
+        *self.config.lock().unwrap() = Some(config);
+    }
+}
+
+/// Messages from loading config.toml that the user should see, kept until
+/// the frontend asks for them since loading starts before it listens
+#[derive(Default)]
+struct ConfigNotices {
+    notices: Mutex<Vec<String>>,
+}
+
+impl ConfigNotices {
+    fn push(&self, notice: String) {
+        self.notices.lock().unwrap().push(notice);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.notices.lock().unwrap())
+    }
+}
+
+/// How long a provider's model list is reused before it is fetched again
+const MODEL_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Model lists keyed by provider name
+#[derive(Default)]
+struct ModelCache {
+    entries: Mutex<HashMap<String, CachedModels>>,
+}
+
+struct CachedModels {
+    fetched: Instant,
+    /// Connection settings the list was fetched with; editing them
+    /// invalidates the entry
+    source: (String, String, String),
+    models: Vec<ModelInfo>,
+}
+
+impl ModelCache {
+    fn get(&self, name: &str, source: &(String, String, String)) -> Option<Vec<ModelInfo>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(name)
+            .filter(|entry| entry.source == *source && entry.fetched.elapsed() < MODEL_CACHE_TTL)
+            .map(|entry| entry.models.clone())
+    }
+
+    fn insert(&self, name: &str, source: (String, String, String), models: Vec<ModelInfo>) {
+        self.entries.lock().unwrap().insert(
+            name.to_string(),
+            CachedModels {
+                fetched: Instant::now(),
+                source,
+                models,
+            },
+        );
+    }
 }
 
 // ============================================================================
@@ -41,21 +346,128 @@ struct ProviderStreamConfig<'a> {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppConfig {
+    /// Schema version, see `schema::CONFIG_VERSION`
+    #[serde(default)]
+    pub version: u32,
     pub default: String,
+    /// Providers tried in order when the requested one fails to connect or authenticate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<String>,
     pub providers: HashMap<String, ProviderConfig>,
+    /// Per-model prices used by the usage ledger, keyed by model id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pricing: HashMap<String, ModelPrice>,
+    #[serde(default, skip_serializing_if = "NetworkConfig::is_default")]
+    pub network: NetworkConfig,
+    /// Profile whose settings `default` and `fallback` currently hold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Named sets of provider choices, e.g. "work" or "offline"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+}
+
+impl AppConfig {
+    /// Sampling parameters for `provider_name`, with the active profile's
+    /// overrides applied
+    fn provider_params(&self, provider_name: &str) -> GenerationParams {
+        let params = self
+            .providers
+            .get(provider_name)
+            .map(|provider| provider.params.clone())
+            .unwrap_or_default();
+
+        match self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+        {
+            Some(profile) => params.with_overrides(&profile.params),
+            None => params,
+        }
+    }
+
+    /// A profile holding the current `default` and `fallback`
+    fn current_profile(&self) -> Profile {
+        let params = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+            .map(|profile| profile.params.clone())
+            .unwrap_or_default();
+
+        Profile {
+            default: self.default.clone(),
+            fallback: self.fallback.clone(),
+            params,
+        }
+    }
+
+    /// Make `name` the active profile, copying its provider choices to the
+    /// top level
+    fn activate_profile(&mut self, name: &str) -> Result<(), String> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| format!("Profile '{}' not found in config", name))?;
+
+        self.default = profile.default.clone();
+        self.fallback = profile.fallback.clone();
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+}
+
+/// Provider choices and parameter overrides saved under a name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub default: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<String>,
+    /// Applied on top of each provider's own parameters
+    #[serde(flatten)]
+    pub params: GenerationParams,
+}
+
+/// Entry returned by `list_profiles`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub default: String,
+    pub active: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     #[serde(rename = "type")]
     pub provider_type: String, // "openai" | "claude" | "gemini"
+    /// May contain `${VAR}` placeholders or be `env:VAR`
     pub base_url: String,
+    /// Plaintext in memory; a `secret:<name>` reference in config.toml, or a
+    /// `${VAR}` / `env:VAR` placeholder
     pub api_key: String,
     pub model: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_window: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub api_version: Option<String>, // Claude needs this
+    pub api_version: Option<String>, // Claude and Azure OpenAI need this
+    /// Default sampling parameters for requests to this provider
+    #[serde(flatten)]
+    pub params: GenerationParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Only used by the "openai" provider type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai_api: Option<OpenAiApi>,
+    /// Azure OpenAI deployment name; defaults to `model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    /// Only used by the "ollama" provider type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ollama: Option<OllamaOptions>,
+    /// Enables extended thinking with this many tokens ("claude" and "gemini")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
 }
 
 // Legacy struct for backward compatibility with frontend
@@ -70,106 +482,509 @@ pub struct LlmConfig {
     pub context_window: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_version: Option<String>,
+    #[serde(flatten)]
+    pub params: GenerationParams,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_api: Option<OpenAiApi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama: Option<OllamaOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a stream event's `delta` contains
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaKind {
+    /// Part of the answer
+    #[default]
+    Text,
+    /// The model's reasoning ("thinking") before the answer
+    Reasoning,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LlmStreamEvent {
     pub stream_id: String,
     pub delta: String,
+    pub kind: DeltaKind,
     pub done: bool,
+    /// Set on the final event of a stream stopped by `cancel_llm_stream`
+    pub cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+    /// Token counts, on the final event when the provider reports them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Why generation stopped, on the final event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+    /// Informational: a failed attempt is about to be retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryNotice>,
+    /// Informational: the provider failed and the next fallback is being tried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<FallbackNotice>,
+    /// Provider that served the response, on the final event
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
+    pub provider: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct OpenAIChoice {
-    delta: Option<OpenAIDelta>,
-    finish_reason: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryNotice {
+    /// The attempt about to start (2 for the first retry)
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    /// Why the previous attempt failed
+    pub reason: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct OpenAIDelta {
-    content: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackNotice {
+    /// Provider that failed
+    pub from: String,
+    /// Provider tried next
+    pub to: String,
+    pub reason: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct OpenAIStreamResponse {
-    choices: Vec<OpenAIChoice>,
+/// Payload of the `config-error` event, emitted when config.toml was edited
+/// outside the app and can no longer be used
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigErrorEvent {
+    pub message: String,
+    /// Field-level errors if the file parsed but failed validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<ValidationReport>,
 }
 
-#[derive(Debug, Deserialize)]
-struct GeminiCandidate {
-    content: Option<GeminiContent>,
+/// Failure of a single streaming attempt
+#[derive(Debug)]
+struct StreamFailure {
+    error: AppError,
+    /// Text had already been emitted, so the attempt cannot be repeated
+    partial: bool,
+    retry_after: Option<Duration>,
 }
 
-#[derive(Debug, Deserialize)]
-struct GeminiContent {
-    parts: Option<Vec<GeminiPart>>,
-}
+impl StreamFailure {
+    /// Rate limits, server errors and network errors may succeed on retry
+    fn retryable(&self) -> bool {
+        !self.partial && self.error.retryable
+    }
 
-#[derive(Debug, Deserialize)]
-struct GeminiPart {
-    text: Option<String>,
+    /// The provider is unreachable, rejects our credentials or keeps failing,
+    /// so another provider may do better
+    fn should_fall_back(&self) -> bool {
+        !self.partial && (self.error.retryable || self.error.kind == ErrorKind::Auth)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct GeminiStreamResponse {
-    candidates: Option<Vec<GeminiCandidate>>,
+impl From<AppError> for StreamFailure {
+    fn from(error: AppError) -> Self {
+        Self {
+            error,
+            partial: false,
+            retry_after: None,
+        }
+    }
 }
 
-// Claude (Anthropic) streaming structures
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct ClaudeStreamEvent {
-    #[serde(rename = "type")]
-    event_type: String,
-    delta: Option<ClaudeDelta>,
-    content_block: Option<ClaudeContentBlock>,
-}
+// ============================================================================
+// Configuration Helpers
+// ============================================================================
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct ClaudeDelta {
-    #[serde(rename = "type")]
-    delta_type: Option<String>,
-    text: Option<String>,
+fn provider_not_found(name: &str) -> AppError {
+    AppError::new(
+        ErrorKind::Config,
+        "provider_not_found",
+        format!("Provider '{}' not found in config", name),
+    )
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct ClaudeContentBlock {
-    #[serde(rename = "type")]
-    block_type: Option<String>,
-    text: Option<String>,
+fn unsupported_provider(provider_type: &str) -> AppError {
+    AppError::new(
+        ErrorKind::Config,
+        "unsupported_provider",
+        format!("Unsupported provider type: {}", provider_type),
+    )
 }
 
-// ============================================================================
-// Configuration Helpers
-// ============================================================================
+/// Environment variable overriding the location of config.toml
+const CONFIG_PATH_ENV: &str = "LITREVIEW_CONFIG";
 
+/// config.toml lives in the per-user app config directory unless
+/// `LITREVIEW_CONFIG` points somewhere else
 fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config dir: {}", e))?;
+
+    Ok(config_dir.join("config.toml"))
+}
+
+/// Where older versions kept config.toml, read once to migrate it
+fn get_legacy_config_path(app: &AppHandle) -> Result<PathBuf, String> {
     let resource_dir = app
         .path()
         .resource_dir()
         .map_err(|e| format!("Failed to get resource dir: {}", e))?;
 
-    // In development, use the project directory
-    // In production, use resource directory
+    // Development builds used the project directory,
+    // production builds the resource directory
     let config_path = if cfg!(debug_assertions) {
-        // Development: use project root
         let mut path = resource_dir.clone();
         // Go up from src-tauri to project root
         path.pop(); // Remove "src-tauri"
         path.push("config.toml");
         path
     } else {
-        // Production: use resource directory
         resource_dir.join("config.toml")
     };
 
     Ok(config_path)
 }
 
+/// Read config.toml, migrating it from the legacy location or creating it
+/// with defaults on first run
+///
+/// API keys are resolved from the secret store. Plaintext keys written by
+/// older versions are moved there on first load and the file is rewritten.
+/// Environment placeholders are resolved last.
+fn read_app_config(app: &AppHandle) -> Result<AppConfig, String> {
+    let config_path = get_config_path(app)?;
+    let mut legacy_source = None;
+
+    if !config_path.exists() {
+        let Some(legacy_path) = get_legacy_config_path(app)
+            .ok()
+            .filter(|path| path.exists())
+        else {
+            // Create default config if not exists
+            let default_config = get_default_config();
+            write_config_file(app, &config_path, &default_config, false)?;
+            return Ok(default_config);
+        };
+
+        // Copy rather than move: the old location is often read-only
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config dir: {}", e))?;
+        }
+        fs::copy(&legacy_path, &config_path)
+            .map_err(|e| format!("Failed to migrate config file: {}", e))?;
+        println!(
+            "[Rust] Migrated config from {} to {}; the old file is no longer used",
+            legacy_path.display(),
+            config_path.display()
+        );
+        legacy_source = Some(legacy_path);
+    }
+
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+
+    let mut table: toml::Table =
+        toml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))?;
+    let schema_migrated = schema::migrate(&mut table)?;
+
+    // Parse the original text when possible, its errors carry line numbers
+    let mut config: AppConfig = if schema_migrated {
+        toml::Value::Table(table).try_into()
+    } else {
+        toml::from_str(&content)
+    }
+    .map_err(|e| format!("Failed to parse config file: {}", e))?;
+
+    let secrets = app.state::<SecretStore>();
+    let has_plaintext_keys = config.providers.values().any(has_plaintext_key);
+
+    if schema_migrated || has_plaintext_keys {
+        let mut migrated = config.clone();
+        let mut keys_stored = has_plaintext_keys;
+        if let Err(e) = store_api_keys(&secrets, &mut migrated) {
+            // Keep working with the plaintext keys if the secret store is unusable
+            println!("[Rust] Failed to move API keys to the secret store: {}", e);
+            migrated = config.clone();
+            keys_stored = false;
+        }
+
+        // A backup of a file with plaintext keys would defeat moving them
+        match write_config_file(app, &config_path, &migrated, !has_plaintext_keys) {
+            Ok(()) => println!(
+                "[Rust] Migrated config to schema version {}",
+                schema::CONFIG_VERSION
+            ),
+            Err(e) => {
+                println!("[Rust] Failed to save migrated config: {}", e);
+                keys_stored = false;
+            }
+        }
+
+        if let Some(legacy_path) = legacy_source.filter(|_| keys_stored) {
+            scrub_legacy_config(app, &legacy_path, &migrated);
+        }
+    }
+
+    resolve_api_keys(&secrets, &mut config);
+    expand_placeholders(&mut config);
+
+    Ok(config)
+}
+
+/// Replace the plaintext API keys in a legacy config.toml that was copied
+/// and migrated, so they no longer sit in a file nothing reads
+///
+/// The user is told either way, since a read-only old file has to be
+/// cleaned up by hand.
+fn scrub_legacy_config(app: &AppHandle, legacy_path: &Path, migrated: &AppConfig) {
+    let scrubbed = fs::read_to_string(legacy_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| config_file::render(Some(&content), migrated))
+        .and_then(|content| fs::write(legacy_path, content).map_err(|e| e.to_string()));
+
+    let notice = match scrubbed {
+        Ok(()) => format!(
+            "API keys were moved to the system keychain and removed from the old config file {}",
+            legacy_path.display()
+        ),
+        Err(e) => format!(
+            "The old config file {} still contains plaintext API keys ({}); delete it or remove its api_key lines",
+            legacy_path.display(),
+            e
+        ),
+    };
+
+    println!("[Rust] {}", notice);
+    app.state::<ConfigNotices>().push(notice);
+}
+
+/// Write config.toml with API keys moved to the secret store and environment
+/// placeholders kept from the file being replaced
+fn write_app_config(app: &AppHandle, config: &AppConfig) -> Result<(), String> {
+    let config_path = get_config_path(app)?;
+    let secrets = app.state::<SecretStore>();
+
+    let previous = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| toml::from_str::<AppConfig>(&content).ok());
+
+    let mut stored = config.clone();
+    stored.version = schema::CONFIG_VERSION;
+    // Changes to the default provider belong to the active profile
+    if let Some(name) = stored.active_profile.clone() {
+        if let Some(profile) = stored.profiles.get_mut(&name) {
+            profile.default = stored.default.clone();
+            profile.fallback = stored.fallback.clone();
+        }
+    }
+    if let Some(previous) = &previous {
+        restore_placeholders(previous, &mut stored);
+    }
+    store_api_keys(&secrets, &mut stored)?;
+    write_config_file(app, &config_path, &stored, true)?;
+
+    // Remove secrets the new config no longer uses (deleted providers,
+    // cleared keys)
+    let previous_secrets = previous.as_ref().map(secret_names).unwrap_or_default();
+    for name in previous_secrets.difference(&secret_names(&stored)) {
+        if let Err(e) = secrets.delete(name) {
+            println!("[Rust] Failed to delete secret '{}': {}", name, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Write `config` over the file at `path`, keeping its comments and layout
+fn write_config_file(
+    app: &AppHandle,
+    path: &Path,
+    config: &AppConfig,
+    backup: bool,
+) -> Result<(), String> {
+    let existing = fs::read_to_string(path).ok();
+    let content = config_file::render(existing.as_deref(), config)?;
+
+    // Saving unchanged settings should not rotate out an older backup
+    if existing.as_deref() == Some(content.as_str()) {
+        return Ok(());
+    }
+
+    save_config_text(app, path, &content, backup)
+}
+
+fn save_config_text(
+    app: &AppHandle,
+    path: &Path,
+    content: &str,
+    backup: bool,
+) -> Result<(), String> {
+    // Not yet managed while the config is first read during setup
+    if let Some(watcher) = app.try_state::<ConfigWatcher>() {
+        watcher.remember(content);
+    }
+
+    config_file::save(path, content, backup)
+}
+
+/// Re-read config.toml after an external edit and push it to the frontend
+///
+/// Emits `config-changed` with the new config, or `config-error` if the file
+/// no longer parses or validates.
+fn reload_config(app: &AppHandle) {
+    println!("[Rust] config.toml changed on disk, reloading");
+
+    let emit_error = |message: String, validation: Option<ValidationReport>| {
+        println!("[Rust] {}", message);
+        let _ = app.emit(
+            "config-error",
+            ConfigErrorEvent {
+                message,
+                validation,
+            },
+        );
+    };
+
+    let config = match read_app_config(app) {
+        Ok(config) => config,
+        Err(e) => return emit_error(e, None),
+    };
+
+    let report = schema::validate(&config, &app.state::<ProviderRegistry>());
+    if !report.is_valid() {
+        return emit_error(
+            format!("Invalid config: {}", report.error_summary()),
+            Some(report),
+        );
+    }
+
+    match network::build_client(&config.network) {
+        Ok(client) => app
+            .state::<HttpClient>()
+            .replace(client, config.network.clone()),
+        Err(e) => return emit_error(e, None),
+    }
+
+    app.state::<LoadedConfig>().set(config.clone());
+    let _ = app.emit("config-changed", config);
+}
+
+/// Read config.toml and keep the result for later streams
+fn refresh_config(app: &AppHandle) -> Result<AppConfig, AppError> {
+    let config = read_app_config(app).map_err(AppError::config)?;
+    app.state::<LoadedConfig>().set(config.clone());
+    Ok(config)
+}
+
+/// The config as last loaded or saved, read from disk only the first time
+fn current_config(app: &AppHandle) -> Result<AppConfig, AppError> {
+    match app.state::<LoadedConfig>().get() {
+        Some(config) => Ok(config),
+        None => refresh_config(app),
+    }
+}
+
+fn has_plaintext_key(provider: &ProviderConfig) -> bool {
+    !provider.api_key.is_empty()
+        && secrets::parse_reference(&provider.api_key).is_none()
+        && !interpolate::has_placeholders(&provider.api_key)
+}
+
+/// Replace plaintext API keys with references to the secret store
+fn store_api_keys(secrets: &SecretStore, config: &mut AppConfig) -> Result<(), String> {
+    for (name, provider) in config.providers.iter_mut() {
+        if has_plaintext_key(provider) {
+            secrets.set(name, &provider.api_key)?;
+            provider.api_key = secrets::reference(name);
+        }
+    }
+    Ok(())
+}
+
+/// Replace secret references with the stored keys
+///
+/// A missing or unreadable secret leaves the key empty so the settings page
+/// can still be used to enter it again.
+fn resolve_api_keys(secrets: &SecretStore, config: &mut AppConfig) {
+    for (name, provider) in config.providers.iter_mut() {
+        let Some(secret_name) = secrets::parse_reference(&provider.api_key) else {
+            continue;
+        };
+
+        provider.api_key = match secrets.get(secret_name) {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                println!("[Rust] No stored API key for provider '{}'", name);
+                String::new()
+            }
+            Err(e) => {
+                println!(
+                    "[Rust] Failed to read API key for provider '{}': {}",
+                    name, e
+                );
+                String::new()
+            }
+        };
+    }
+}
+
+/// Resolve environment placeholders in provider connection settings
+///
+/// An unset variable resolves to an empty string so the rest of the config
+/// still loads.
+fn expand_placeholders(config: &mut AppConfig) {
+    for (name, provider) in config.providers.iter_mut() {
+        for value in [&mut provider.base_url, &mut provider.api_key] {
+            if interpolate::has_placeholders(value) {
+                *value = interpolate::expand(value).unwrap_or_else(|e| {
+                    println!("[Rust] Provider '{}': {}", name, e);
+                    String::new()
+                });
+            }
+        }
+    }
+}
+
+/// Put placeholders from the previous file back in place of the values they
+/// resolved to, so values taken from the environment are never written out;
+/// a value the user has changed is saved as entered
+fn restore_placeholders(previous: &AppConfig, config: &mut AppConfig) {
+    for (name, provider) in config.providers.iter_mut() {
+        let Some(old) = previous.providers.get(name) else {
+            continue;
+        };
+
+        for (value, raw) in [
+            (&mut provider.base_url, &old.base_url),
+            (&mut provider.api_key, &old.api_key),
+        ] {
+            if interpolate::has_placeholders(raw)
+                && interpolate::expand(raw).unwrap_or_default() == *value
+            {
+                value.clone_from(raw);
+            }
+        }
+    }
+}
+
+fn secret_names(config: &AppConfig) -> HashSet<String> {
+    config
+        .providers
+        .values()
+        .filter_map(|provider| secrets::parse_reference(&provider.api_key))
+        .map(str::to_string)
+        .collect()
+}
+
 fn get_default_config() -> AppConfig {
     let mut providers = HashMap::new();
 
@@ -182,6 +997,15 @@ fn get_default_config() -> AppConfig {
             model: "gpt-4o".to_string(),
             context_window: Some(128000),
             api_version: None,
+            params: GenerationParams {
+                temperature: Some(0.3),
+                ..Default::default()
+            },
+            retry: None,
+            openai_api: None,
+            deployment: None,
+            ollama: None,
+            thinking_budget: None,
         },
     );
 
@@ -194,6 +1018,15 @@ fn get_default_config() -> AppConfig {
             model: "claude-sonnet-4-20250514".to_string(),
             context_window: Some(200000),
             api_version: Some("2023-06-01".to_string()),
+            params: GenerationParams {
+                max_tokens: Some(8192),
+                ..Default::default()
+            },
+            retry: None,
+            openai_api: None,
+            deployment: None,
+            ollama: None,
+            thinking_budget: None,
         },
     );
 
@@ -206,12 +1039,27 @@ fn get_default_config() -> AppConfig {
             model: "gemini-1.5-flash".to_string(),
             context_window: Some(1000000),
             api_version: None,
+            params: GenerationParams {
+                temperature: Some(0.3),
+                ..Default::default()
+            },
+            retry: None,
+            openai_api: None,
+            deployment: None,
+            ollama: None,
+            thinking_budget: None,
         },
     );
 
     AppConfig {
+        version: schema::CONFIG_VERSION,
         default: "openai".to_string(),
+        fallback: Vec::new(),
         providers,
+        pricing: HashMap::new(),
+        network: NetworkConfig::default(),
+        active_profile: None,
+        profiles: HashMap::new(),
     }
 }
 
@@ -222,12 +1070,17 @@ fn get_default_config() -> AppConfig {
 /// Start a streaming LLM request
 /// Returns stream_id immediately, emits 'llm-stream' events as data arrives
 #[tauri::command]
-async fn start_llm_stream(app: AppHandle, config: StreamRequestConfig) -> Result<String, String> {
+async fn start_llm_stream(
+    app: AppHandle,
+    streams: State<'_, ActiveStreams>,
+    config: StreamRequestConfig,
+) -> Result<String, AppError> {
     println!("[Rust] start_llm_stream called");
     println!("[Rust] provider_type: {}", config.provider_type);
     println!("[Rust] model: {}", config.model);
     println!("[Rust] base_url: {}", config.base_url);
     println!("[Rust] prompt length: {}", config.prompt.len());
+    println!("[Rust] history messages: {}", config.messages.len());
     println!(
         "[Rust] system_prompt: {:?}",
         config.system_prompt.as_ref().map(|s| s.len())
@@ -236,186 +1089,247 @@ async fn start_llm_stream(app: AppHandle, config: StreamRequestConfig) -> Result
     let stream_id = Uuid::new_v4().to_string();
     let stream_id_clone = stream_id.clone();
 
-    // Spawn async task to handle streaming
-    tauri::async_runtime::spawn(async move {
-        let system = config.system_prompt.clone();
-        println!(
-            "[Rust] Spawned task, system_prompt len: {:?}",
-            system.as_ref().map(|s| s.len())
-        );
-        let provider_config = ProviderStreamConfig {
-            stream_id: &stream_id_clone,
-            base_url: &config.base_url,
-            api_key: &config.api_key,
-            model: &config.model,
-            prompt: &config.prompt,
-            api_version: config.api_version.as_deref(),
-            system_prompt: system.as_deref(),
-        };
-
-        let result = match config.provider_type.as_str() {
-            "openai" => {
-                stream_openai_compatible(
-                    &app,
-                    provider_config.stream_id,
-                    provider_config.base_url,
-                    provider_config.api_key,
-                    provider_config.model,
-                    provider_config.prompt,
-                    provider_config.system_prompt,
-                )
-                .await
-            }
-            "claude" => stream_claude(&app, provider_config).await,
-            "gemini" => {
-                stream_gemini(
-                    &app,
-                    provider_config.stream_id,
-                    provider_config.base_url,
-                    provider_config.api_key,
-                    provider_config.model,
-                    provider_config.prompt,
-                    provider_config.system_prompt,
-                )
-                .await
-            }
-            _ => Err(format!(
-                "Unsupported provider type: {}",
-                config.provider_type
-            )),
-        };
+    // Hold the lock while spawning so the task cannot deregister itself
+    // before its handle has been stored
+    let mut tasks = streams.streams.lock().unwrap();
 
-        if let Err(e) = result {
+    // Spawn async task to handle streaming
+    let handle = tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_stream(&app, &stream_id_clone, config).await {
             let _ = app.emit(
                 "llm-stream",
                 LlmStreamEvent {
-                    stream_id: stream_id_clone,
-                    delta: String::new(),
+                    stream_id: stream_id_clone.clone(),
                     done: true,
                     error: Some(e),
+                    ..Default::default()
                 },
             );
         }
+
+        app.state::<ActiveStreams>().remove(&stream_id_clone);
     });
 
-    Ok(stream_id)
+    tasks.insert(
+        stream_id.clone(),
+        ActiveStream {
+            task: handle,
+            attempt: None,
+        },
+    );
+
+    Ok(stream_id)
+}
+
+/// Cancel a running stream, aborting its HTTP request
+/// Returns false if the stream already finished
+#[tauri::command]
+async fn cancel_llm_stream(
+    app: AppHandle,
+    streams: State<'_, ActiveStreams>,
+    stream_id: String,
+) -> Result<bool, AppError> {
+    let Some(stream) = streams.remove(&stream_id) else {
+        return Ok(false);
+    };
+
+    stream.task.abort();
+
+    // Whatever was generated before the abort is still billed
+    if let Some(attempt) = stream.attempt {
+        let pricing = app
+            .state::<LoadedConfig>()
+            .get()
+            .map(|config| config.pricing)
+            .unwrap_or_default();
+        record_usage(
+            &app,
+            &attempt.config,
+            &attempt.summary,
+            attempt.started.elapsed(),
+            &pricing,
+            true,
+        );
+    }
+
+    let _ = app.emit(
+        "llm-stream",
+        LlmStreamEvent {
+            stream_id,
+            done: true,
+            cancelled: true,
+            ..Default::default()
+        },
+    );
+
+    Ok(true)
 }
 
 /// Get the path to the TOML config file
 #[tauri::command]
-async fn get_config_file_path(app: AppHandle) -> Result<String, String> {
-    let path = get_config_path(&app)?;
+async fn get_config_file_path(app: AppHandle) -> Result<String, AppError> {
+    let path = get_config_path(&app).map_err(AppError::storage)?;
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Notices from loading config.toml, such as a legacy file migration
+/// Each is returned only once
+#[tauri::command]
+async fn take_config_notices(notices: State<'_, ConfigNotices>) -> Result<Vec<String>, AppError> {
+    Ok(notices.take())
+}
+
 /// Load the full AppConfig from TOML file
 #[tauri::command]
-async fn load_toml_config(app: AppHandle) -> Result<AppConfig, String> {
-    let config_path = get_config_path(&app)?;
+async fn load_toml_config(app: AppHandle) -> Result<AppConfig, AppError> {
+    refresh_config(&app)
+}
 
-    if !config_path.exists() {
-        // Create default config if not exists
-        let default_config = get_default_config();
-        let toml_str = toml::to_string_pretty(&default_config)
-            .map_err(|e| format!("Failed to serialize default config: {}", e))?;
-        fs::write(&config_path, toml_str)
-            .map_err(|e| format!("Failed to write default config: {}", e))?;
-        return Ok(default_config);
+/// Save the full AppConfig to TOML file
+/// Fails if `validate_config` reports errors
+#[tauri::command]
+async fn save_toml_config(app: AppHandle, config: AppConfig) -> Result<(), AppError> {
+    let report = schema::validate(&config, &app.state::<ProviderRegistry>());
+    if !report.is_valid() {
+        return Err(AppError::new(
+            ErrorKind::Config,
+            "config_invalid",
+            format!("Invalid config: {}", report.error_summary()),
+        ));
     }
 
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    // Build the new client first so an invalid [network] section is rejected
+    // before anything is written
+    let client = network::build_client(&config.network).map_err(AppError::config)?;
 
-    let config: AppConfig =
-        toml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))?;
+    write_app_config(&app, &config).map_err(AppError::storage)?;
 
-    Ok(config)
+    app.state::<HttpClient>()
+        .replace(client, config.network.clone());
+    app.state::<LoadedConfig>().set(config);
+
+    Ok(())
 }
 
-/// Save the full AppConfig to TOML file
+/// Check a config for errors and warnings without saving it
 #[tauri::command]
-async fn save_toml_config(app: AppHandle, config: AppConfig) -> Result<(), String> {
-    let config_path = get_config_path(&app)?;
+async fn validate_config(
+    registry: State<'_, ProviderRegistry>,
+    config: AppConfig,
+) -> Result<ValidationReport, AppError> {
+    Ok(schema::validate(&config, &registry))
+}
 
-    let toml_str = toml::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+/// Saved copies of config.toml, newest first
+#[tauri::command]
+async fn list_config_backups(app: AppHandle) -> Result<Vec<ConfigBackup>, AppError> {
+    get_config_path(&app)
+        .and_then(|path| config_file::list_backups(&path))
+        .map_err(AppError::storage)
+}
 
-    fs::write(&config_path, toml_str).map_err(|e| format!("Failed to write config file: {}", e))?;
+/// Replace config.toml with one of its backups and return the restored config
+/// The current file is backed up first, so a restore can itself be undone
+#[tauri::command]
+async fn restore_config_backup(app: AppHandle, name: String) -> Result<AppConfig, AppError> {
+    let config_path = get_config_path(&app).map_err(AppError::storage)?;
+    let content = config_file::read_backup(&config_path, &name).map_err(AppError::storage)?;
+
+    // Refuse a backup this version cannot load rather than break the config
+    let check = || -> Result<(), String> {
+        let mut table: toml::Table =
+            toml::from_str(&content).map_err(|e| format!("Failed to parse backup: {}", e))?;
+        schema::migrate(&mut table)?;
+        toml::Value::Table(table)
+            .try_into::<AppConfig>()
+            .map_err(|e| format!("Failed to parse backup: {}", e))?;
+        Ok(())
+    };
+    check().map_err(|e| AppError::new(ErrorKind::Config, "backup_invalid", e))?;
 
-    Ok(())
-}
+    save_config_text(&app, &config_path, &content, true).map_err(AppError::storage)?;
 
-fn handle_gemini_json_response(
-    app: &AppHandle,
-    stream_id: &str,
-    body_text: &str,
-) -> Result<(), String> {
-    let json: serde_json::Value =
-        serde_json::from_str(body_text).map_err(|e| format!("Invalid JSON response: {}", e))?;
-
-    if let Some(error) = json.get("error") {
-        let message = error
-            .get("message")
-            .and_then(|m| m.as_str())
-            .or_else(|| error.as_str())
-            .unwrap_or("Unknown error");
-        return Err(format!("API error: {}", message));
+    let config = refresh_config(&app)?;
+    if let Ok(client) = network::build_client(&config.network) {
+        app.state::<HttpClient>()
+            .replace(client, config.network.clone());
     }
 
-    let mut emitted = false;
+    Ok(config)
+}
 
-    if let Some(candidates) = json.get("candidates").and_then(|c| c.as_array()) {
-        for candidate in candidates {
-            if let Some(content) = candidate.get("content") {
-                if let Some(parts) = content.get("parts").and_then(|p| p.as_array()) {
-                    for part in parts {
-                        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                            emitted = true;
-                            let _ = app.emit(
-                                "llm-stream",
-                                LlmStreamEvent {
-                                    stream_id: stream_id.to_string(),
-                                    delta: text.to_string(),
-                                    done: false,
-                                    error: None,
-                                },
-                            );
-                        }
-                    }
-                }
-            }
-        }
+/// Write the providers to a shareable file in the downloads folder and
+/// return its path
+/// API keys are left out unless `include_keys` is set
+#[tauri::command]
+async fn export_config(app: AppHandle, include_keys: bool) -> Result<String, AppError> {
+    let mut config = read_app_config(&app).map_err(AppError::config)?;
+
+    // Share `env:VAR` rather than the value it resolved to on this machine
+    let raw = fs::read_to_string(get_config_path(&app).map_err(AppError::storage)?)
+        .ok()
+        .and_then(|content| toml::from_str::<AppConfig>(&content).ok());
+    if let Some(raw) = &raw {
+        restore_placeholders(raw, &mut config);
     }
 
-    if !emitted {
-        // Emit raw body text as fallback
-        let _ = app.emit(
-            "llm-stream",
-            LlmStreamEvent {
-                stream_id: stream_id.to_string(),
-                delta: body_text.to_string(),
-                done: false,
-                error: None,
-            },
-        );
-    }
+    let content = portable::export(&config, include_keys).map_err(AppError::config)?;
 
-    let _ = app.emit(
-        "llm-stream",
-        LlmStreamEvent {
-            stream_id: stream_id.to_string(),
-            delta: String::new(),
-            done: true,
-            error: None,
-        },
-    );
+    let dir = app
+        .path()
+        .download_dir()
+        .or_else(|_| app.path().home_dir())
+        .map_err(|e| AppError::storage(format!("Failed to get downloads dir: {}", e)))?;
+    let path = dir.join(format!(
+        "litreview-providers-{}.toml",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, content)
+        .map_err(|e| AppError::storage(format!("Failed to write export: {}", e)))?;
+
+    println!("[Rust] Exported providers to {}", path.display());
+    Ok(path.to_string_lossy().to_string())
+}
 
-    Ok(())
+/// Result of `import_config`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    #[serde(flatten)]
+    pub summary: ImportSummary,
+    pub config: AppConfig,
+}
+
+/// Merge the providers of an exported file (or a config.toml) into the config
+/// Providers whose name is already taken are handled per `on_conflict`
+#[tauri::command]
+async fn import_config(
+    app: AppHandle,
+    content: String,
+    on_conflict: Option<ConflictStrategy>,
+) -> Result<ImportResult, AppError> {
+    let imported = portable::parse(&content).map_err(AppError::invalid_input)?;
+    if imported.providers.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "no_providers",
+            "The file contains no providers",
+        ));
+    }
+
+    let mut config = load_toml_config(app.clone()).await?;
+    let summary = portable::merge(&mut config, imported, on_conflict.unwrap_or_default());
+    save_toml_config(app.clone(), config).await?;
+
+    // Reload so placeholders in the imported providers are resolved
+    Ok(ImportResult {
+        summary,
+        config: refresh_config(&app)?,
+    })
 }
 
 /// Get the current active LLM config (for backward compatibility)
 #[tauri::command]
-async fn get_active_config(app: AppHandle) -> Result<Option<LlmConfig>, String> {
+async fn get_active_config(app: AppHandle) -> Result<Option<LlmConfig>, AppError> {
     let app_config = load_toml_config(app).await?;
 
     let provider_name = &app_config.default;
@@ -428,6 +1342,11 @@ async fn get_active_config(app: AppHandle) -> Result<Option<LlmConfig>, String>
             model: provider.model.clone(),
             context_window: provider.context_window,
             api_version: provider.api_version.clone(),
+            params: app_config.provider_params(provider_name),
+            openai_api: provider.openai_api,
+            deployment: provider.deployment.clone(),
+            ollama: provider.ollama.clone(),
+            thinking_budget: provider.thinking_budget,
         })),
         None => Ok(None),
     }
@@ -435,615 +1354,585 @@ async fn get_active_config(app: AppHandle) -> Result<Option<LlmConfig>, String>
 
 /// Set the default (active) provider
 #[tauri::command]
-async fn set_default_provider(app: AppHandle, provider_name: String) -> Result<(), String> {
+async fn set_default_provider(app: AppHandle, provider_name: String) -> Result<(), AppError> {
     let mut config = load_toml_config(app.clone()).await?;
 
     if !config.providers.contains_key(&provider_name) {
-        return Err(format!("Provider '{}' not found in config", provider_name));
+        return Err(provider_not_found(&provider_name));
     }
 
     config.default = provider_name;
     save_toml_config(app, config).await
 }
 
-/// Test LLM connection with a minimal request (non-streaming)
-/// Returns Ok(()) if connection succeeds, Err with details if it fails
+/// Named profiles, sorted by name
 #[tauri::command]
-async fn test_llm_connection(
-    provider_type: String,
-    base_url: String,
-    api_key: String,
-    model: String,
-    api_version: Option<String>,
-) -> Result<(), String> {
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-    match provider_type.as_str() {
-        "openai" => {
-            let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
-            let body = serde_json::json!({
-                "model": model,
-                "messages": [{"role": "user", "content": "ping"}],
-                "max_tokens": 1,
-                "temperature": 0.0
-            });
+async fn list_profiles(app: AppHandle) -> Result<Vec<ProfileSummary>, AppError> {
+    let config = load_toml_config(app).await?;
+
+    let mut profiles: Vec<ProfileSummary> = config
+        .profiles
+        .iter()
+        .map(|(name, profile)| ProfileSummary {
+            name: name.clone(),
+            default: profile.default.clone(),
+            active: config.active_profile.as_deref() == Some(name.as_str()),
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(profiles)
+}
 
-            let mut request = client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
+/// Save the current provider choices as a new profile and switch to it
+/// `default` picks a different default provider for the new profile
+#[tauri::command]
+async fn create_profile(
+    app: AppHandle,
+    name: String,
+    default: Option<String>,
+) -> Result<AppConfig, AppError> {
+    let mut config = load_toml_config(app.clone()).await?;
 
-            if !api_key.is_empty() {
-                request = request.header("Authorization", format!("Bearer {}", api_key));
-            }
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Profile name must not be empty"));
+    }
+    if config.profiles.contains_key(&name) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "profile_exists",
+            format!("Profile '{}' already exists", name),
+        ));
+    }
 
-            let response = request
-                .send()
-                .await
-                .map_err(|e| format!("网络错误: {}", e))?;
-
-            if response.status().is_success() {
-                Ok(())
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_default();
-                // Truncate error text to avoid huge messages
-                let snippet = if error_text.len() > 200 {
-                    format!("{}...", &error_text[..200])
-                } else {
-                    error_text
-                };
-                Err(format!("HTTP {}: {}", status, snippet))
-            }
+    let mut profile = config.current_profile();
+    if let Some(default) = default {
+        if !config.providers.contains_key(&default) {
+            return Err(provider_not_found(&default));
         }
-        "claude" => {
-            let url = format!("{}/v1/messages", base_url.trim_end_matches('/'));
-            let version = api_version.unwrap_or_else(|| "2023-06-01".to_string());
-            let body = serde_json::json!({
-                "model": model,
-                "messages": [{"role": "user", "content": "ping"}],
-                "max_tokens": 1
-            });
+        profile.default = default;
+    }
 
-            let response = client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .header("x-api-key", &api_key)
-                .header("anthropic-version", &version)
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| format!("网络错误: {}", e))?;
-
-            if response.status().is_success() {
-                Ok(())
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_default();
-                let snippet = if error_text.len() > 200 {
-                    format!("{}...", &error_text[..200])
-                } else {
-                    error_text
-                };
-                Err(format!("HTTP {}: {}", status, snippet))
-            }
-        }
-        "gemini" => {
-            let url = format!(
-                "{}/v1beta/models/{}:generateContent?key={}",
-                base_url.trim_end_matches('/'),
-                model,
-                api_key
-            );
-            let body = serde_json::json!({
-                "contents": [{
-                    "parts": [{"text": "ping"}]
-                }],
-                "generationConfig": {
-                    "maxOutputTokens": 1
-                }
-            });
+    config.profiles.insert(name.clone(), profile);
+    config
+        .activate_profile(&name)
+        .map_err(|e| AppError::new(ErrorKind::Config, "profile_not_found", e))?;
+    save_toml_config(app, config.clone()).await?;
 
-            let response = client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| format!("网络错误: {}", e))?;
-
-            if response.status().is_success() {
-                Ok(())
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_default();
-                let snippet = if error_text.len() > 200 {
-                    format!("{}...", &error_text[..200])
-                } else {
-                    error_text
-                };
-                Err(format!("HTTP {}: {}", status, snippet))
-            }
-        }
-        _ => Err(format!("不支持的 provider 类型: {}", provider_type)),
-    }
+    Ok(config)
 }
 
-// ============================================================================
-// Streaming Implementations
-// ============================================================================
+/// Make `name` the active profile
+#[tauri::command]
+async fn switch_profile(app: AppHandle, name: String) -> Result<AppConfig, AppError> {
+    let mut config = load_toml_config(app.clone()).await?;
 
-/// Stream from OpenAI-compatible API (OpenAI, Ollama, DeepSeek, Moonshot, etc.)
-async fn stream_openai_compatible(
-    app: &AppHandle,
-    stream_id: &str,
-    base_url: &str,
-    api_key: &str,
-    model: &str,
-    prompt: &str,
-    system_prompt: Option<&str>,
-) -> Result<(), String> {
-    let client = Client::new();
-    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    config
+        .activate_profile(&name)
+        .map_err(|e| AppError::new(ErrorKind::Config, "profile_not_found", e))?;
+    save_toml_config(app, config.clone()).await?;
 
-    // Build messages array with optional system prompt
-    let mut messages = Vec::new();
-    if let Some(system) = system_prompt {
-        messages.push(serde_json::json!({"role": "system", "content": system}));
+    Ok(config)
+}
+
+/// Copy profile `source` to a new profile called `name`
+/// The active profile does not change
+#[tauri::command]
+async fn duplicate_profile(
+    app: AppHandle,
+    source: String,
+    name: String,
+) -> Result<AppConfig, AppError> {
+    let mut config = load_toml_config(app.clone()).await?;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Profile name must not be empty"));
+    }
+    if config.profiles.contains_key(&name) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "profile_exists",
+            format!("Profile '{}' already exists", name),
+        ));
     }
-    messages.push(serde_json::json!({"role": "user", "content": prompt}));
 
-    let body = serde_json::json!({
-        "model": model,
-        "messages": messages,
-        "stream": true,
-        "temperature": 0.3
-    });
+    // `default` and `fallback` are the live copy of the active profile
+    let profile = if config.active_profile.as_deref() == Some(source.as_str()) {
+        config.current_profile()
+    } else {
+        config.profiles.get(&source).cloned().ok_or_else(|| {
+            AppError::new(
+                ErrorKind::Config,
+                "profile_not_found",
+                format!("Profile '{}' not found in config", source),
+            )
+        })?
+    };
+
+    config.profiles.insert(name, profile);
+    save_toml_config(app, config.clone()).await?;
+
+    Ok(config)
+}
+
+/// Check a provider step by step: DNS, TCP and TLS timings, a minimal
+/// request, then a short streamed one for time to first token
+/// Failures are reported in the returned report, not as `Err`
+#[tauri::command]
+async fn test_llm_connection(
+    app: AppHandle,
+    provider_name: String,
+    provider: ProviderConfig,
+) -> Result<DiagnosticReport, AppError> {
+    let config = StreamRequestConfig::for_request(Some(&provider_name), &provider);
+    let provider = app
+        .state::<ProviderRegistry>()
+        .get(&provider.provider_type)
+        .ok_or_else(|| unsupported_provider(&provider.provider_type))?;
+
+    // The settings the shared client was built from, so the probes match it
+    let http = app.state::<HttpClient>();
+    let (client, network) = (http.get(), http.network());
+
+    let mut report = diagnostics::run(&client, &network, provider, &config).await;
+    if let Some(error) = &mut report.error {
+        error.error.provider.clone_from(&config.provider);
+        println!(
+            "[Rust] Connection test failed at {:?}: {:?} {}",
+            error.step, error.error.kind, error.error.message
+        );
+    }
 
-    let mut request = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&body);
+    Ok(report)
+}
 
-    // Add auth header if api_key is provided (Ollama may not need it)
-    if !api_key.is_empty() {
-        request = request.header("Authorization", format!("Bearer {}", api_key));
+/// Models offered by a configured provider, fetched from its model list
+/// endpoint and cached per provider
+/// `refresh` bypasses the cache
+#[tauri::command]
+async fn list_models(
+    app: AppHandle,
+    provider_name: String,
+    refresh: Option<bool>,
+) -> Result<Vec<ModelInfo>, AppError> {
+    let app_config = load_toml_config(app.clone()).await?;
+    let provider_config = app_config
+        .providers
+        .get(&provider_name)
+        .ok_or_else(|| provider_not_found(&provider_name))?;
+
+    let source = (
+        provider_config.provider_type.clone(),
+        provider_config.base_url.clone(),
+        provider_config.api_key.clone(),
+    );
+    let cache = app.state::<ModelCache>();
+    if !refresh.unwrap_or(false) {
+        if let Some(models) = cache.get(&provider_name, &source) {
+            return Ok(models);
+        }
     }
 
-    let response = request
+    let provider = app
+        .state::<ProviderRegistry>()
+        .get(&provider_config.provider_type)
+        .ok_or_else(|| unsupported_provider(&provider_config.provider_type))?;
+
+    let config = StreamRequestConfig::for_request(Some(&provider_name), provider_config);
+
+    let client = app.state::<HttpClient>().get();
+    let response = provider
+        .build_list_models(&client, &config)
+        .timeout(Duration::from_secs(30))
         .send()
         .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .map_err(|e| AppError::request(&e).with_provider(Some(&provider_name)))?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("HTTP {}: {}", status, error_text));
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| AppError::request(&e).with_provider(Some(&provider_name)))?;
+    if !status.is_success() {
+        return Err(AppError::http(status, body).with_provider(Some(&provider_name)));
     }
 
-    let is_sse = response
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|value| value.to_str().ok())
-        .map(|value| value.contains("text/event-stream"))
-        .unwrap_or(false);
-
-    if !is_sse {
-        let body_text = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to read response body: {}", e))?;
-        return handle_openai_json_response(app, stream_id, &body_text);
-    }
+    let models = provider.parse_models(&body).map_err(|e| {
+        AppError::invalid_response(e)
+            .with_provider(Some(&provider_name))
+            .with_raw(body)
+    })?;
+    println!(
+        "[Rust] Listed {} models for provider {}",
+        models.len(),
+        provider_name
+    );
+    cache.insert(&provider_name, source, models.clone());
 
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    Ok(models)
+}
 
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let chunk_str = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&chunk_str);
+/// Aggregate the usage ledger by day, provider, model or feature
+/// `since` is an inclusive local date (YYYY-MM-DD)
+#[tauri::command]
+async fn get_usage_stats(
+    ledger: State<'_, UsageLedger>,
+    group_by: UsageGrouping,
+    since: Option<chrono::NaiveDate>,
+) -> Result<Vec<UsageBucket>, AppError> {
+    ledger.summarize(group_by, since).map_err(AppError::storage)
+}
 
-        // Process complete SSE lines
-        while let Some(line_end) = buffer.find('\n') {
-            let line = buffer[..line_end].trim().to_string();
-            buffer = buffer[line_end + 1..].to_string();
+// ============================================================================
+// Streaming Implementations
+// ============================================================================
 
-            if line.is_empty() || line.starts_with(':') {
-                continue;
-            }
+/// Splits a streamed response body into the payloads providers parse
+enum StreamDecoder {
+    Sse(SseDecoder),
+    Ndjson(NdjsonDecoder),
+}
 
-            if line == "data: [DONE]" {
-                let _ = app.emit(
-                    "llm-stream",
-                    LlmStreamEvent {
-                        stream_id: stream_id.to_string(),
-                        delta: String::new(),
-                        done: true,
-                        error: None,
-                    },
-                );
-                return Ok(());
-            }
+impl StreamDecoder {
+    /// The decoder for a response's Content-Type, or `None` for a plain body
+    fn for_response(response: &reqwest::Response) -> Option<Self> {
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if content_type.contains("text/event-stream") {
+            Some(Self::Sse(SseDecoder::new()))
+        } else if content_type.contains("ndjson") {
+            Some(Self::Ndjson(NdjsonDecoder::new()))
+        } else {
+            None
+        }
+    }
 
-            if let Some(data) = line.strip_prefix("data: ") {
-                if let Ok(parsed) = serde_json::from_str::<OpenAIStreamResponse>(data) {
-                    for choice in parsed.choices {
-                        if let Some(delta) = choice.delta {
-                            if let Some(content) = delta.content {
-                                if !content.is_empty() {
-                                    let _ = app.emit(
-                                        "llm-stream",
-                                        LlmStreamEvent {
-                                            stream_id: stream_id.to_string(),
-                                            delta: content,
-                                            done: false,
-                                            error: None,
-                                        },
-                                    );
-                                }
-                            }
-                        }
-                        if choice.finish_reason.is_some() {
-                            let _ = app.emit(
-                                "llm-stream",
-                                LlmStreamEvent {
-                                    stream_id: stream_id.to_string(),
-                                    delta: String::new(),
-                                    done: true,
-                                    error: None,
-                                },
-                            );
-                            return Ok(());
-                        }
-                    }
-                }
-            }
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        match self {
+            Self::Sse(decoder) => decoder.feed(chunk).into_iter().map(|e| e.data).collect(),
+            Self::Ndjson(decoder) => decoder.feed(chunk),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<String> {
+        match self {
+            Self::Sse(decoder) => decoder.finish().into_iter().map(|e| e.data).collect(),
+            Self::Ndjson(decoder) => decoder.finish(),
         }
     }
+}
+
+/// Append a completed or cancelled stream to the usage ledger
+fn record_usage(
+    app: &AppHandle,
+    config: &StreamRequestConfig,
+    summary: &StreamSummary,
+    elapsed: Duration,
+    pricing: &HashMap<String, ModelPrice>,
+    cancelled: bool,
+) {
+    let usage = summary.usage.unwrap_or_default();
+
+    let entry = LedgerEntry {
+        timestamp: chrono::Local::now(),
+        provider: config
+            .provider
+            .clone()
+            .unwrap_or_else(|| config.provider_type.clone()),
+        provider_type: config.provider_type.clone(),
+        model: config.model.clone(),
+        feature: config.feature.clone(),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        duration_ms: elapsed.as_millis() as u64,
+        finish_reason: summary.finish_reason,
+        cost: summary
+            .usage
+            .and_then(|usage| pricing.get(&config.model).map(|price| price.cost(usage))),
+        cancelled,
+    };
 
-    // Send done if stream ends without explicit [DONE]
+    if let Err(e) = app.state::<UsageLedger>().append(&entry) {
+        println!("[Rust] Failed to record usage: {}", e);
+    }
+}
+
+fn emit_delta(app: &AppHandle, stream_id: &str, kind: DeltaKind, delta: String) {
     let _ = app.emit(
         "llm-stream",
         LlmStreamEvent {
             stream_id: stream_id.to_string(),
-            delta: String::new(),
-            done: true,
-            error: None,
+            delta,
+            kind,
+            ..Default::default()
         },
     );
+}
 
-    Ok(())
+fn emit_done(app: &AppHandle, stream_id: &str, provider: Option<&str>, summary: &StreamSummary) {
+    let _ = app.emit(
+        "llm-stream",
+        LlmStreamEvent {
+            stream_id: stream_id.to_string(),
+            done: true,
+            provider: provider.map(str::to_string),
+            usage: summary.usage,
+            finish_reason: summary.finish_reason,
+            ..Default::default()
+        },
+    );
 }
 
-fn handle_openai_json_response(
+/// Stream `config`, then each configured fallback provider in turn while
+/// failures are ones another provider may not have
+async fn run_stream(
     app: &AppHandle,
     stream_id: &str,
-    body_text: &str,
-) -> Result<(), String> {
-    let json: serde_json::Value =
-        serde_json::from_str(body_text).map_err(|e| format!("Invalid JSON response: {}", e))?;
-
-    if let Some(error) = json.get("error") {
-        let message = error
-            .get("message")
-            .and_then(|m| m.as_str())
-            .or_else(|| error.as_str())
-            .unwrap_or("Unknown error");
-        return Err(format!("API error: {}", message));
+    config: StreamRequestConfig,
+) -> Result<(), AppError> {
+    let registry = app.state::<ProviderRegistry>();
+    if registry.get(&config.provider_type).is_none() {
+        return Err(unsupported_provider(&config.provider_type));
     }
 
-    let mut emitted = false;
-
-    if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
-        for choice in choices {
-            if let Some(message_content) = choice
-                .get("message")
-                .and_then(|message| message.get("content"))
-                .and_then(|content| content.as_str())
-            {
-                emitted = true;
-                let _ = app.emit(
-                    "llm-stream",
-                    LlmStreamEvent {
-                        stream_id: stream_id.to_string(),
-                        delta: message_content.to_string(),
-                        done: false,
-                        error: None,
-                    },
-                );
-            } else if let Some(text) = choice.get("text").and_then(|text| text.as_str()) {
-                emitted = true;
-                let _ = app.emit(
-                    "llm-stream",
-                    LlmStreamEvent {
-                        stream_id: stream_id.to_string(),
-                        delta: text.to_string(),
-                        done: false,
-                        error: None,
-                    },
-                );
+    let app_config = current_config(app)?;
+    let client = app.state::<HttpClient>().get();
+
+    // Fallbacks only apply to requests made on behalf of a named provider
+    let mut candidates = Vec::new();
+    if let Some(primary) = &config.provider {
+        for name in app_config.fallback.iter().filter(|name| *name != primary) {
+            match app_config.providers.get(name) {
+                Some(provider) if registry.get(&provider.provider_type).is_some() => {
+                    let mut candidate = config.for_provider(name, provider);
+                    candidate.params = app_config.provider_params(name);
+                    candidates.push(candidate);
+                }
+                _ => println!("[Rust] Skipping unknown fallback provider: {}", name),
             }
         }
     }
+    candidates.insert(0, config);
 
-    if !emitted {
-        if let Some(result) = json
-            .get("result")
-            .and_then(|value| value.get("response"))
-            .and_then(|value| value.as_str())
-        {
-            emitted = true;
-            let _ = app.emit(
-                "llm-stream",
-                LlmStreamEvent {
-                    stream_id: stream_id.to_string(),
-                    delta: result.to_string(),
-                    done: false,
-                    error: None,
-                },
-            );
-        }
-    }
+    for (index, candidate) in candidates.iter().enumerate() {
+        let Some(provider) = registry.get(&candidate.provider_type) else {
+            continue;
+        };
+        let policy = candidate
+            .provider
+            .as_ref()
+            .and_then(|name| app_config.providers.get(name)?.retry.clone())
+            .unwrap_or_default();
+
+        let started = Instant::now();
+        let mut failure =
+            match stream_with_retry(app, &client, stream_id, provider, candidate, &policy).await {
+                Ok(summary) => {
+                    if app.state::<ActiveStreams>().end_attempt(stream_id) {
+                        record_usage(
+                            app,
+                            candidate,
+                            &summary,
+                            started.elapsed(),
+                            &app_config.pricing,
+                            false,
+                        );
+                    }
+                    return Ok(());
+                }
+                Err(failure) => failure,
+            };
+        failure.error.provider.clone_from(&candidate.provider);
+
+        let next = match candidates.get(index + 1) {
+            Some(next) if failure.should_fall_back() => next,
+            _ => return Err(failure.error),
+        };
+
+        let from = candidate.provider.clone().unwrap_or_default();
+        let to = next.provider.clone().unwrap_or_default();
+        println!(
+            "[Rust] Provider {} failed, falling back to {}: {}",
+            from, to, failure.error
+        );
 
-    if !emitted {
-        // Emit raw body text to help with debugging unknown response formats
         let _ = app.emit(
             "llm-stream",
             LlmStreamEvent {
                 stream_id: stream_id.to_string(),
-                delta: body_text.to_string(),
-                done: false,
-                error: None,
+                fallback: Some(FallbackNotice {
+                    from,
+                    to,
+                    reason: failure.error.message,
+                }),
+                ..Default::default()
             },
         );
     }
 
-    let _ = app.emit(
-        "llm-stream",
-        LlmStreamEvent {
-            stream_id: stream_id.to_string(),
-            delta: String::new(),
-            done: true,
-            error: None,
-        },
-    );
-
+    // The last candidate always returns above
     Ok(())
 }
 
-/// Stream from Google Gemini API
-async fn stream_gemini(
+/// Run `stream_with_provider`, retrying transient failures that happen before
+/// any delta was emitted
+async fn stream_with_retry(
     app: &AppHandle,
+    client: &Client,
     stream_id: &str,
-    base_url: &str,
-    api_key: &str,
-    model: &str,
-    prompt: &str,
-    system_prompt: Option<&str>,
-) -> Result<(), String> {
-    let client = Client::new();
-    let url = format!(
-        "{}/v1beta/models/{}:streamGenerateContent?key={}&alt=sse",
-        base_url.trim_end_matches('/'),
-        model,
-        api_key
-    );
-
-    // Build body with optional system instruction
-    let mut body = serde_json::json!({
-        "contents": [{
-            "parts": [{"text": prompt}]
-        }],
-        "generationConfig": {
-            "temperature": 0.3
+    provider: Arc<dyn LlmProvider>,
+    config: &StreamRequestConfig,
+    policy: &RetryPolicy,
+) -> Result<StreamSummary, StreamFailure> {
+    let mut attempt = 1;
+
+    loop {
+        let failure =
+            match stream_with_provider(app, client, stream_id, provider.clone(), config).await {
+                Ok(summary) => return Ok(summary),
+                Err(failure) => failure,
+            };
+
+        if !failure.retryable() || attempt >= policy.max_attempts {
+            return Err(failure);
         }
-    });
 
-    // Add system instruction if provided
-    if let Some(system) = system_prompt {
-        body["systemInstruction"] = serde_json::json!({
-            "parts": [{"text": system}]
-        });
-    }
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("HTTP {}: {}", status, error_text));
-    }
-
-    let is_sse = response
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|value| value.to_str().ok())
-        .map(|value| value.contains("text/event-stream"))
-        .unwrap_or(false);
-
-    if !is_sse {
-        let body_text = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to read response body: {}", e))?;
-        return handle_gemini_json_response(app, stream_id, &body_text);
-    }
-
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
-
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let chunk_str = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&chunk_str);
-
-        // Process complete SSE lines
-        while let Some(line_end) = buffer.find('\n') {
-            let line = buffer[..line_end].trim().to_string();
-            buffer = buffer[line_end + 1..].to_string();
+        let delay = policy.delay(attempt, failure.retry_after);
+        attempt += 1;
+        println!(
+            "[Rust] Retrying stream {} (attempt {}/{}) in {:?}: {}",
+            stream_id, attempt, policy.max_attempts, delay, failure.error
+        );
 
-            if line.is_empty() || line.starts_with(':') {
-                continue;
-            }
+        let _ = app.emit(
+            "llm-stream",
+            LlmStreamEvent {
+                stream_id: stream_id.to_string(),
+                retry: Some(RetryNotice {
+                    attempt,
+                    max_attempts: policy.max_attempts,
+                    delay_ms: delay.as_millis() as u64,
+                    reason: failure.error.message.clone(),
+                }),
+                ..Default::default()
+            },
+        );
 
-            if let Some(data) = line.strip_prefix("data: ") {
-                if let Ok(parsed) = serde_json::from_str::<GeminiStreamResponse>(data) {
-                    if let Some(candidates) = parsed.candidates {
-                        for candidate in candidates {
-                            if let Some(content) = candidate.content {
-                                if let Some(parts) = content.parts {
-                                    for part in parts {
-                                        if let Some(text) = part.text {
-                                            if !text.is_empty() {
-                                                let _ = app.emit(
-                                                    "llm-stream",
-                                                    LlmStreamEvent {
-                                                        stream_id: stream_id.to_string(),
-                                                        delta: text,
-                                                        done: false,
-                                                        error: None,
-                                                    },
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        tokio::time::sleep(delay).await;
     }
-
-    // Send done when stream ends
-    let _ = app.emit(
-        "llm-stream",
-        LlmStreamEvent {
-            stream_id: stream_id.to_string(),
-            delta: String::new(),
-            done: true,
-            error: None,
-        },
-    );
-
-    Ok(())
 }
 
-/// Stream from Claude (Anthropic) API
-async fn stream_claude(app: &AppHandle, config: ProviderStreamConfig<'_>) -> Result<(), String> {
-    let api_version = config.api_version.unwrap_or("2023-06-01");
-    let client = Client::new();
-    let url = format!("{}/v1/messages", config.base_url.trim_end_matches('/'));
-
-    // Build body with optional system prompt
-    let mut body = serde_json::json!({
-        "model": config.model,
-        "max_tokens": 4096,
-        "messages": [{"role": "user", "content": config.prompt}],
-        "stream": true
-    });
-
-    // Add system prompt if provided (Claude uses top-level "system" field)
-    if let Some(system) = config.system_prompt {
-        body["system"] = serde_json::json!(system);
-    }
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("x-api-key", config.api_key)
-        .header("anthropic-version", api_version)
-        .json(&body)
+/// Run a streaming request through the given provider, emitting 'llm-stream' events
+/// Returns the usage and finish reason reported on the final event
+async fn stream_with_provider(
+    app: &AppHandle,
+    client: &Client,
+    stream_id: &str,
+    provider: Arc<dyn LlmProvider>,
+    config: &StreamRequestConfig,
+) -> Result<StreamSummary, StreamFailure> {
+    let streams = app.state::<ActiveStreams>();
+    streams.begin_attempt(stream_id, config);
+
+    let response = provider
+        .build_stream_request(client, config)
         .send()
         .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .map_err(|e| AppError::request(&e))?;
 
     if !response.status().is_success() {
         let status = response.status();
+        let retry_after = retry::retry_after(response.headers());
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("HTTP {}: {}", status, error_text));
+        return Err(StreamFailure {
+            error: AppError::http(status, error_text),
+            partial: false,
+            retry_after,
+        });
     }
 
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
-
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let chunk_str = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&chunk_str);
+    let Some(mut decoder) = StreamDecoder::for_response(&response) else {
+        let body_text = response.text().await.map_err(|e| AppError::request(&e))?;
+        let parsed = provider
+            .parse_response_body(&body_text)
+            .map_err(|e| AppError::invalid_response(e).with_raw(body_text.clone()))?;
+        let mut summary = StreamSummary::default();
+        summary.record(&parsed);
+        streams.record(stream_id, &parsed);
+
+        if parsed.deltas.is_empty() && parsed.reasoning.is_empty() {
+            // Emit raw body text to help with debugging unknown response formats
+            emit_delta(app, stream_id, DeltaKind::Text, body_text);
+        }
+        for text in parsed.reasoning {
+            emit_delta(app, stream_id, DeltaKind::Reasoning, text);
+        }
+        for text in parsed.deltas {
+            emit_delta(app, stream_id, DeltaKind::Text, text);
+        }
 
-        // Process complete SSE lines
-        while let Some(line_end) = buffer.find('\n') {
-            let line = buffer[..line_end].trim().to_string();
-            buffer = buffer[line_end + 1..].to_string();
+        emit_done(app, stream_id, config.provider.as_deref(), &summary);
+        return Ok(summary);
+    };
 
-            if line.is_empty() || line.starts_with(':') {
-                continue;
+    let mut stream = response.bytes_stream();
+    let mut summary = StreamSummary::default();
+    let mut emitted = false;
+    let mut finished = false;
+
+    while !finished {
+        let events = match stream.next().await {
+            Some(chunk_result) => {
+                let chunk = chunk_result.map_err(|e| StreamFailure {
+                    error: AppError::request(&e),
+                    // Retrying after text reached the frontend would duplicate it
+                    partial: emitted,
+                    retry_after: None,
+                })?;
+                decoder.feed(&chunk)
+            }
+            None => {
+                finished = true;
+                decoder.finish()
             }
+        };
 
-            // Claude SSE format: event: xxx\ndata: {...}
-            if let Some(data) = line.strip_prefix("data: ") {
-                if let Ok(parsed) = serde_json::from_str::<ClaudeStreamEvent>(data) {
-                    match parsed.event_type.as_str() {
-                        "content_block_delta" => {
-                            if let Some(delta) = parsed.delta {
-                                if let Some(text) = delta.text {
-                                    if !text.is_empty() {
-                                        let _ = app.emit(
-                                            "llm-stream",
-                                            LlmStreamEvent {
-                                                stream_id: config.stream_id.to_string(),
-                                                delta: text,
-                                                done: false,
-                                                error: None,
-                                            },
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        "message_stop" => {
-                            let _ = app.emit(
-                                "llm-stream",
-                                LlmStreamEvent {
-                                    stream_id: config.stream_id.to_string(),
-                                    delta: String::new(),
-                                    done: true,
-                                    error: None,
-                                },
-                            );
-                            return Ok(());
-                        }
-                        _ => {}
-                    }
-                }
+        for data in events {
+            let parsed = provider.parse_stream_data(&data);
+            summary.record(&parsed);
+            streams.record(stream_id, &parsed);
+            for delta in parsed.reasoning {
+                emitted = true;
+                emit_delta(app, stream_id, DeltaKind::Reasoning, delta);
+            }
+            for delta in parsed.deltas {
+                emitted = true;
+                emit_delta(app, stream_id, DeltaKind::Text, delta);
+            }
+            if let Some(error) = parsed.error {
+                return Err(StreamFailure {
+                    error,
+                    partial: emitted,
+                    retry_after: None,
+                });
+            }
+            if parsed.done {
+                emit_done(app, stream_id, config.provider.as_deref(), &summary);
+                return Ok(summary);
             }
         }
     }
 
-    // Send done when stream ends
-    let _ = app.emit(
-        "llm-stream",
-        LlmStreamEvent {
-            stream_id: config.stream_id.to_string(),
-            delta: String::new(),
-            done: true,
-            error: None,
-        },
-    );
+    // Send done if stream ends without an explicit end marker
+    emit_done(app, stream_id, config.provider.as_deref(), &summary);
 
-    Ok(())
+    Ok(summary)
 }
 
 // ============================================================================
@@ -1053,16 +1942,69 @@ async fn stream_claude(app: &AppHandle, config: ProviderStreamConfig<'_>) -> Res
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .manage(ProviderRegistry::default())
+        .manage(ActiveStreams::default())
+        .manage(ModelCache::default())
+        .manage(LoadedConfig::default())
+        .manage(ConfigNotices::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(UsageLedger::new(data_dir.join("usage.jsonl")));
+            app.manage(SecretStore::new(data_dir));
+
+            // A broken config must not prevent the app from starting; the
+            // settings page can still fix it
+            let network = match refresh_config(app.handle()) {
+                Ok(config) => config.network,
+                Err(e) => {
+                    println!("[Rust] {}", e);
+                    NetworkConfig::default()
+                }
+            };
+            let (client, network) = match network::build_client(&network) {
+                Ok(client) => (client, network),
+                Err(e) => {
+                    println!("[Rust] {}, using default HTTP client", e);
+                    (Client::new(), NetworkConfig::default())
+                }
+            };
+            app.manage(HttpClient::new(client, network));
+
+            let handle = app.handle().clone();
+            let watcher = get_config_path(app.handle())
+                .and_then(|path| ConfigWatcher::start(path, move || reload_config(&handle)));
+            match watcher {
+                Ok(watcher) => {
+                    app.manage(watcher);
+                }
+                Err(e) => println!("[Rust] {}, external config edits need a restart", e),
+            }
+
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             start_llm_stream,
+            cancel_llm_stream,
             get_config_file_path,
+            take_config_notices,
             load_toml_config,
             save_toml_config,
+            validate_config,
+            list_config_backups,
+            restore_config_backup,
+            export_config,
+            import_config,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            duplicate_profile,
             get_active_config,
             set_default_provider,
-            test_llm_connection
+            test_llm_connection,
+            list_models,
+            get_usage_stats
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,82 @@
+//! Newline-delimited JSON decoder, used by Ollama's streaming API.
+//!
+//! Each non-empty line is one payload; buffering works as in [`crate::sse`].
+
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a network chunk, returning the lines it completes
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        while let Some(offset) = self.buffer[start..].iter().position(|&b| b == b'\n') {
+            let end = start + offset;
+            push_line(&mut lines, &self.buffer[start..end]);
+            start = end + 1;
+        }
+
+        self.buffer.drain(..start);
+
+        lines
+    }
+
+    /// Flush a last line that is not terminated by a newline
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        push_line(&mut lines, &self.buffer);
+        self.buffer.clear();
+        lines
+    }
+}
+
+fn push_line(lines: &mut Vec<String>, bytes: &[u8]) {
+    let line = String::from_utf8_lossy(bytes);
+    let line = line.trim();
+    if !line.is_empty() {
+        lines.push(line.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<String> {
+        let mut decoder = NdjsonDecoder::new();
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            lines.extend(decoder.feed(chunk));
+        }
+        lines.extend(decoder.finish());
+        lines
+    }
+
+    #[test]
+    fn splits_lines_and_skips_blank_ones() {
+        let lines = decode_chunks(&[b"{\"a\":1}\r\n\n{\"b\":2}\n"]);
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"b\":2}"]);
+    }
+
+    #[test]
+    fn keeps_cjk_split_across_chunks() {
+        let payload = "{\"response\":\"文献\"}\n".as_bytes();
+        let lines = decode_chunks(&[&payload[..15], &payload[15..]]);
+        assert_eq!(lines, vec!["{\"response\":\"文献\"}"]);
+    }
+
+    #[test]
+    fn flushes_unterminated_last_line() {
+        let lines = decode_chunks(&[b"{\"a\":1}\n{\"done\":", b"true}"]);
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"done\":true}"]);
+    }
+}
@@ -0,0 +1,168 @@
+//! Shared HTTP client built from the `[network]` section of config.toml.
+//!
+//! A single `reqwest::Client` is kept in managed state so connection pools are
+//! reused across requests, and is rebuilt whenever the config is saved.
+
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::sync::RwLock;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for all requests, e.g. "http://proxy.example.edu:8080";
+    /// unset means the system proxy environment variables apply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass `proxy`, e.g. "localhost,127.0.0.1"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// Maximum time between two reads, so slow streams are not cut off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout_secs: Option<u64>,
+    /// PEM file with extra root certificates, e.g. a university CA
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    /// Accept invalid TLS certificates (self-signed local servers only)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub insecure: bool,
+}
+
+impl NetworkConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+pub fn build_client(config: &NetworkConfig) -> Result<Client, String> {
+    let mut builder = Client::builder();
+
+    if let Some(proxy_url) = config.proxy.as_deref().filter(|url| !url.is_empty()) {
+        let proxy = Proxy::all(proxy_url)
+            .map_err(|e| format!("Invalid proxy URL '{}': {}", proxy_url, e))?
+            .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(secs) = config.connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+
+    if let Some(secs) = config.read_timeout_secs {
+        builder = builder.read_timeout(Duration::from_secs(secs));
+    }
+
+    for certificate in load_ca_bundle(config, Certificate::from_pem)? {
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    if config.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Parse each certificate in `config.ca_bundle` with `parse`
+///
+/// Shared by the client and the diagnostics TLS probe so both trust the same
+/// roots. Returns nothing when no bundle is configured.
+pub fn load_ca_bundle<T, E: Display>(
+    config: &NetworkConfig,
+    parse: impl Fn(&[u8]) -> Result<T, E>,
+) -> Result<Vec<T>, String> {
+    let Some(path) = config.ca_bundle.as_deref().filter(|path| !path.is_empty()) else {
+        return Ok(Vec::new());
+    };
+
+    let pem = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read CA bundle '{}': {}", path, e))?;
+    let certificates = pem_certificates(&pem)
+        .map(|block| parse(block.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid CA bundle '{}': {}", path, e))?;
+
+    if certificates.is_empty() {
+        return Err(format!(
+            "Invalid CA bundle '{}': no certificates found",
+            path
+        ));
+    }
+
+    Ok(certificates)
+}
+
+/// The certificate blocks of a PEM file, markers included
+fn pem_certificates(pem: &str) -> impl Iterator<Item = &str> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    pem.match_indices(BEGIN).filter_map(move |(start, _)| {
+        let end = start + pem[start..].find(END)? + END.len();
+        Some(&pem[start..end])
+    })
+}
+
+/// The shared client and the settings it was built from, held in Tauri
+/// managed state
+pub struct HttpClient {
+    state: RwLock<(Client, NetworkConfig)>,
+}
+
+impl HttpClient {
+    pub fn new(client: Client, network: NetworkConfig) -> Self {
+        Self {
+            state: RwLock::new((client, network)),
+        }
+    }
+
+    /// A handle to the current client (cheap, clients are reference-counted)
+    pub fn get(&self) -> Client {
+        self.state.read().unwrap().0.clone()
+    }
+
+    /// The `[network]` settings the current client was built from
+    pub fn network(&self) -> NetworkConfig {
+        self.state.read().unwrap().1.clone()
+    }
+
+    pub fn replace(&self, client: Client, network: NetworkConfig) {
+        *self.state.write().unwrap() = (client, network);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_pem_bundle_into_certificates() {
+        let pem = "\
+# University CA
+-----BEGIN CERTIFICATE-----
+AAAA
+-----END CERTIFICATE-----
+-----BEGIN CERTIFICATE-----
+BBBB
+-----END CERTIFICATE-----
+-----BEGIN CERTIFICATE-----
+truncated
+";
+
+        let blocks: Vec<_> = pem_certificates(pem).collect();
+        assert_eq!(
+            blocks,
+            [
+                "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----",
+                "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----",
+            ]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Provider setups exported for sharing.
+//!
+//! An export holds the providers and prices from config.toml in the same
+//! TOML layout, without machine-specific sections such as `[network]`. API
+//! keys are left out unless asked for; environment placeholders are kept, as
+//! they name a variable rather than hold a secret. A full config.toml can be
+//! imported the same way.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::usage::ModelPrice;
+use crate::{interpolate, schema, secrets, AppConfig, ProviderConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedConfig {
+    /// Schema version of the provider entries, see `schema::CONFIG_VERSION`
+    #[serde(default)]
+    pub version: u32,
+    pub providers: HashMap<String, ProviderConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pricing: HashMap<String, ModelPrice>,
+}
+
+/// What to do with an imported provider whose name is already in use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Keep the existing provider
+    #[default]
+    Skip,
+    /// Overwrite the existing provider, keeping its key if the import has none
+    Replace,
+    /// Add the imported provider under a free name such as "openai-2"
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamedProvider {
+    pub from: String,
+    pub to: String,
+}
+
+/// Outcome of merging an export into a config
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub renamed: Vec<RenamedProvider>,
+    pub skipped: Vec<String>,
+}
+
+/// Render the shareable part of `config`
+///
+/// `config` should hold placeholders rather than their resolved values.
+pub fn export(config: &AppConfig, include_keys: bool) -> Result<String, String> {
+    let mut providers = config.providers.clone();
+    for provider in providers.values_mut() {
+        if !include_keys && !interpolate::has_placeholders(&provider.api_key) {
+            provider.api_key.clear();
+        }
+    }
+
+    let exported = ExportedConfig {
+        version: schema::CONFIG_VERSION,
+        providers,
+        pricing: config.pricing.clone(),
+    };
+    let content = toml::to_string_pretty(&exported)
+        .map_err(|e| format!("Failed to serialize export: {}", e))?;
+
+    let header = if include_keys {
+        "# LitReview Pro provider export. Contains API keys, do not share publicly.\n\n"
+    } else {
+        "# LitReview Pro provider export. API keys are not included.\n\n"
+    };
+    Ok(format!("{}{}", header, content))
+}
+
+/// Parse an export or a config.toml
+pub fn parse(content: &str) -> Result<ExportedConfig, String> {
+    let mut table: toml::Table =
+        toml::from_str(content).map_err(|e| format!("Failed to parse import: {}", e))?;
+    schema::migrate(&mut table)?;
+
+    let mut imported: ExportedConfig = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| format!("Failed to parse import: {}", e))?;
+
+    // References point into the secret store of the machine that wrote them
+    for provider in imported.providers.values_mut() {
+        if secrets::parse_reference(&provider.api_key).is_some() {
+            provider.api_key.clear();
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Add the providers and prices of `imported` to `config`
+pub fn merge(
+    config: &mut AppConfig,
+    imported: ExportedConfig,
+    strategy: ConflictStrategy,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+
+    let mut providers: Vec<_> = imported.providers.into_iter().collect();
+    providers.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (name, mut provider) in providers {
+        let Some(existing) = config.providers.get(&name) else {
+            config.providers.insert(name.clone(), provider);
+            summary.added.push(name);
+            continue;
+        };
+
+        match strategy {
+            ConflictStrategy::Skip => summary.skipped.push(name),
+            ConflictStrategy::Replace => {
+                if provider.api_key.is_empty() {
+                    provider.api_key.clone_from(&existing.api_key);
+                }
+                config.providers.insert(name.clone(), provider);
+                summary.replaced.push(name);
+            }
+            ConflictStrategy::Rename => {
+                let to = (2..)
+                    .map(|n| format!("{}-{}", name, n))
+                    .find(|candidate| !config.providers.contains_key(candidate))
+                    .unwrap_or_default();
+                config.providers.insert(to.clone(), provider);
+                summary.renamed.push(RenamedProvider { from: name, to });
+            }
+        }
+    }
+
+    for (model, price) in imported.pricing {
+        if strategy == ConflictStrategy::Replace || !config.pricing.contains_key(&model) {
+            config.pricing.insert(model, price);
+        }
+    }
+
+    summary
+}
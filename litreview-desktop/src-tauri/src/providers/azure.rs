@@ -0,0 +1,185 @@
+use reqwest::{Client, RequestBuilder, Url};
+
+use super::openai::{health_check_body, stream_body};
+use super::{parse_json_body, LlmProvider, ModelInfo, OpenAiProvider, ParsedChunk};
+use crate::StreamRequestConfig;
+
+/// Used when the provider has no `api_version`
+const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// First dated api-version that accepts `stream_options.include_usage`
+const STREAM_USAGE_API_VERSION: &str = "2024-09-01";
+
+fn api_version(config: &StreamRequestConfig) -> &str {
+    config
+        .api_version
+        .as_deref()
+        .filter(|version| !version.is_empty())
+        .unwrap_or(DEFAULT_API_VERSION)
+}
+
+/// Older versions reject the whole request when asked for usage
+fn supports_stream_usage(api_version: &str) -> bool {
+    // Dates compare as strings; "-preview" suffixes do not matter, and
+    // undated versions ("preview", "latest") are newer than any dated one
+    match api_version.get(..10).filter(|date| date.starts_with("20")) {
+        Some(date) => date >= STREAM_USAGE_API_VERSION,
+        None => true,
+    }
+}
+
+/// `{base}/openai/{segments}` with each segment percent-encoded, since
+/// deployment names are user-chosen
+fn url(base_url: &str, segments: &[&str]) -> String {
+    // An unparsable base URL is reported when the request is sent
+    Url::parse(base_url)
+        .ok()
+        .and_then(|mut url| {
+            url.path_segments_mut()
+                .ok()?
+                .pop_if_empty()
+                .push("openai")
+                .extend(segments);
+            Some(url.to_string())
+        })
+        .unwrap_or_else(|| base_url.to_string())
+}
+
+/// Azure OpenAI Service: OpenAI chat completions behind per-deployment URLs
+pub struct AzureOpenAiProvider;
+
+impl AzureOpenAiProvider {
+    /// `{base}/openai/{segments}?api-version=...` with `api-key` auth
+    fn request(
+        client: &Client,
+        method: reqwest::Method,
+        config: &StreamRequestConfig,
+        segments: &[&str],
+    ) -> RequestBuilder {
+        client
+            .request(method, url(&config.base_url, segments))
+            .query(&[("api-version", api_version(config))])
+            .header("api-key", &config.api_key)
+    }
+
+    fn post(
+        client: &Client,
+        config: &StreamRequestConfig,
+        body: serde_json::Value,
+    ) -> RequestBuilder {
+        // Deployments are often named after their model
+        let deployment = config
+            .deployment
+            .as_deref()
+            .filter(|deployment| !deployment.is_empty())
+            .unwrap_or(&config.model);
+        let segments = ["deployments", deployment, "chat", "completions"];
+
+        Self::request(client, reqwest::Method::POST, config, &segments)
+            .header("Content-Type", "application/json")
+            .json(&body)
+    }
+}
+
+impl LlmProvider for AzureOpenAiProvider {
+    fn provider_type(&self) -> &'static str {
+        "azure_openai"
+    }
+
+    fn build_stream_request(
+        &self,
+        client: &Client,
+        config: &StreamRequestConfig,
+    ) -> RequestBuilder {
+        let mut body = stream_body(config);
+        if !supports_stream_usage(api_version(config)) {
+            if let Some(body) = body.as_object_mut() {
+                body.remove("stream_options");
+            }
+        }
+        Self::post(client, config, body)
+    }
+
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        Self::post(client, config, health_check_body(config))
+    }
+
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk {
+        OpenAiProvider.parse_stream_data(data)
+    }
+
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String> {
+        OpenAiProvider.parse_response_body(body)
+    }
+
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        Self::request(client, reqwest::Method::GET, config, &["models"])
+    }
+
+    /// Models the resource can deploy, not its deployments
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String> {
+        let json = parse_json_body(body)?;
+        let entries = json
+            .get("data")
+            .and_then(|data| data.as_array())
+            .ok_or("Unexpected model list response")?;
+
+        let mut models: Vec<ModelInfo> = entries
+            .iter()
+            .filter_map(|entry| {
+                Some(ModelInfo {
+                    id: entry.get("id")?.as_str()?.to_string(),
+                    display_name: None,
+                    context_window: None,
+                    max_output_tokens: None,
+                    // e.g. {"chat_completion": true, "embeddings": false}
+                    capabilities: entry
+                        .get("capabilities")
+                        .and_then(|capabilities| capabilities.as_object())
+                        .map(|capabilities| {
+                            capabilities
+                                .iter()
+                                .filter(|(_, enabled)| enabled.as_bool() == Some(true))
+                                .map(|(name, _)| name.clone())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        models.dedup_by(|a, b| a.id == b.id);
+
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_deployment_in_path() {
+        assert_eq!(
+            url(
+                "https://example.openai.azure.com/",
+                &["deployments", "gpt 4o/mini", "chat", "completions"]
+            ),
+            "https://example.openai.azure.com/openai/deployments/gpt%204o%2Fmini/chat/completions"
+        );
+        assert_eq!(
+            url("https://example.openai.azure.com", &["models"]),
+            "https://example.openai.azure.com/openai/models"
+        );
+    }
+
+    #[test]
+    fn requests_usage_only_from_supporting_versions() {
+        assert!(supports_stream_usage(DEFAULT_API_VERSION));
+        assert!(supports_stream_usage("2024-09-01-preview"));
+        assert!(supports_stream_usage("2025-04-01-preview"));
+        assert!(supports_stream_usage("preview"));
+        assert!(!supports_stream_usage("2024-08-01-preview"));
+        assert!(!supports_stream_usage("2024-06-01"));
+    }
+}
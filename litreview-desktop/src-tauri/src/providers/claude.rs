@@ -0,0 +1,358 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+use super::{
+    json_u32, parse_json_body, FinishReason, LlmProvider, ModelInfo, ParsedChunk, TokenUsage,
+};
+use crate::error::AppError;
+use crate::StreamRequestConfig;
+
+const DEFAULT_API_VERSION: &str = "2023-06-01";
+/// Claude requires `max_tokens`; used when neither the request nor the provider sets it
+/// (on top of the thinking budget when extended thinking is enabled)
+const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+// Claude (Anthropic) streaming structures
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ClaudeStreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    delta: Option<ClaudeDelta>,
+    content_block: Option<ClaudeContentBlock>,
+    /// Present on `message_start`
+    message: Option<ClaudeMessage>,
+    /// Present on `message_delta`
+    usage: Option<ClaudeUsage>,
+    /// Present on `error`, e.g. `{"type": "overloaded_error", "message": ...}`
+    error: Option<serde_json::Value>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ClaudeDelta {
+    #[serde(rename = "type")]
+    delta_type: Option<String>,
+    text: Option<String>,
+    /// `thinking_delta` of an extended thinking block
+    thinking: Option<String>,
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClaudeMessage {
+    usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+impl From<ClaudeUsage> for TokenUsage {
+    fn from(usage: ClaudeUsage) -> Self {
+        TokenUsage {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        }
+    }
+}
+
+fn map_stop_reason(reason: &str) -> FinishReason {
+    match reason {
+        "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "max_tokens" => FinishReason::Length,
+        "refusal" => FinishReason::ContentFilter,
+        "tool_use" => FinishReason::ToolUse,
+        _ => FinishReason::Other,
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ClaudeContentBlock {
+    #[serde(rename = "type")]
+    block_type: Option<String>,
+    text: Option<String>,
+}
+
+/// Claude (Anthropic) Messages API
+pub struct ClaudeProvider;
+
+impl ClaudeProvider {
+    fn post(
+        client: &Client,
+        config: &StreamRequestConfig,
+        body: serde_json::Value,
+    ) -> RequestBuilder {
+        let url = format!("{}/v1/messages", config.base_url.trim_end_matches('/'));
+        let api_version = config.api_version.as_deref().unwrap_or(DEFAULT_API_VERSION);
+
+        client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", api_version)
+            .json(&body)
+    }
+}
+
+impl LlmProvider for ClaudeProvider {
+    fn provider_type(&self) -> &'static str {
+        "claude"
+    }
+
+    fn build_stream_request(
+        &self,
+        client: &Client,
+        config: &StreamRequestConfig,
+    ) -> RequestBuilder {
+        let messages: Vec<serde_json::Value> = config
+            .conversation()
+            .into_iter()
+            .map(|message| serde_json::json!({"role": message.role, "content": message.content}))
+            .collect();
+
+        // Thinking tokens count towards max_tokens, so the default leaves
+        // room for the answer on top of the budget
+        let params = &config.params;
+        let thinking_budget = config.thinking_budget;
+        let max_tokens = params.max_tokens.unwrap_or(match thinking_budget {
+            Some(budget) => budget + DEFAULT_MAX_TOKENS,
+            None => DEFAULT_MAX_TOKENS,
+        });
+
+        // Build body with optional system prompt
+        let mut body = serde_json::json!({
+            "model": config.model,
+            "max_tokens": max_tokens,
+            "messages": messages,
+            "stream": true
+        });
+
+        // Extended thinking rejects a modified temperature or top_p
+        if let Some(budget) = thinking_budget {
+            body["thinking"] = serde_json::json!({"type": "enabled", "budget_tokens": budget});
+        } else {
+            if let Some(temperature) = params.temperature {
+                body["temperature"] = serde_json::json!(temperature);
+            }
+            if let Some(top_p) = params.top_p {
+                body["top_p"] = serde_json::json!(top_p);
+            }
+        }
+        if let Some(stop) = &params.stop_sequences {
+            body["stop_sequences"] = serde_json::json!(stop);
+        }
+
+        // Add system prompt if provided (Claude uses top-level "system" field)
+        if let Some(system) = config.system_text() {
+            body["system"] = serde_json::json!(system);
+        }
+
+        Self::post(client, config, body)
+    }
+
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let body = serde_json::json!({
+            "model": config.model,
+            "messages": [{"role": "user", "content": "ping"}],
+            "max_tokens": 1
+        });
+
+        Self::post(client, config, body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk {
+        let mut chunk = ParsedChunk::default();
+
+        // Claude SSE format: event: xxx\ndata: {...}
+        if let Ok(parsed) = serde_json::from_str::<ClaudeStreamEvent>(data) {
+            match parsed.event_type.as_str() {
+                "content_block_delta" => {
+                    if let Some(delta) = parsed.delta {
+                        if let Some(text) = delta.text.filter(|text| !text.is_empty()) {
+                            chunk.deltas.push(text);
+                        }
+                        if let Some(thinking) = delta.thinking.filter(|text| !text.is_empty()) {
+                            chunk.reasoning.push(thinking);
+                        }
+                    }
+                }
+                "message_start" => {
+                    chunk.usage = parsed
+                        .message
+                        .and_then(|message| message.usage)
+                        .map(TokenUsage::from);
+                }
+                "message_delta" => {
+                    chunk.finish_reason = parsed
+                        .delta
+                        .and_then(|delta| delta.stop_reason)
+                        .map(|reason| map_stop_reason(&reason));
+                    chunk.usage = parsed.usage.map(TokenUsage::from);
+                }
+                "message_stop" => chunk.done = true,
+                "error" => {
+                    chunk.error = Some(AppError::stream(&parsed.error.unwrap_or_default()));
+                    chunk.done = true;
+                }
+                _ => {}
+            }
+        }
+
+        chunk
+    }
+
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String> {
+        let json = parse_json_body(body)?;
+        let mut chunk = ParsedChunk {
+            done: true,
+            ..Default::default()
+        };
+
+        if let Some(blocks) = json.get("content").and_then(|c| c.as_array()) {
+            for block in blocks {
+                if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                    chunk.deltas.push(text.to_string());
+                }
+                if let Some(thinking) = block.get("thinking").and_then(|t| t.as_str()) {
+                    chunk.reasoning.push(thinking.to_string());
+                }
+            }
+        }
+
+        chunk.finish_reason = json
+            .get("stop_reason")
+            .and_then(|r| r.as_str())
+            .map(map_stop_reason);
+        chunk.usage = json
+            .get("usage")
+            .cloned()
+            .and_then(|usage| serde_json::from_value::<ClaudeUsage>(usage).ok())
+            .map(TokenUsage::from);
+
+        Ok(chunk)
+    }
+
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let url = format!("{}/v1/models", config.base_url.trim_end_matches('/'));
+        let api_version = config.api_version.as_deref().unwrap_or(DEFAULT_API_VERSION);
+
+        client
+            .get(&url)
+            .query(&[("limit", "1000")])
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", api_version)
+    }
+
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String> {
+        let json = parse_json_body(body)?;
+        let entries = json
+            .get("data")
+            .and_then(|data| data.as_array())
+            .ok_or("Unexpected model list response")?;
+
+        let mut models: Vec<ModelInfo> = entries
+            .iter()
+            .filter_map(|entry| {
+                Some(ModelInfo {
+                    id: entry.get("id")?.as_str()?.to_string(),
+                    display_name: entry
+                        .get("display_name")
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string),
+                    context_window: json_u32(entry, "max_input_tokens"),
+                    max_output_tokens: json_u32(entry, "max_tokens"),
+                    capabilities: Vec::new(),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::providers::test_support::parse_stream;
+
+    #[test]
+    fn merges_usage_from_start_and_delta_events() {
+        let parsed = parse_stream(
+            &ClaudeProvider,
+            &[
+                r#"{"type":"message_start","message":{"usage":{"input_tokens":25,"output_tokens":1}}}"#,
+                r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+                r#"{"type":"ping"}"#,
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#,
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":" world"}}"#,
+                r#"{"type":"content_block_stop","index":0}"#,
+                r#"{"type":"message_delta","delta":{"stop_reason":"max_tokens"},"usage":{"output_tokens":15}}"#,
+                r#"{"type":"message_stop"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello world");
+        assert!(parsed.done);
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Length));
+        assert_eq!(
+            parsed.summary.usage,
+            Some(TokenUsage {
+                input_tokens: 25,
+                output_tokens: 15
+            })
+        );
+    }
+
+    #[test]
+    fn separates_thinking_from_text() {
+        let parsed = parse_stream(
+            &ClaudeProvider,
+            &[
+                r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Let me think"}}"#,
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"EqQB"}}"#,
+                r#"{"type":"content_block_stop","index":0}"#,
+                r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Answer"}}"#,
+                r#"{"type":"message_stop"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.reasoning, "Let me think");
+        assert_eq!(parsed.text, "Answer");
+    }
+
+    #[test]
+    fn stops_at_overloaded_error_event() {
+        let parsed = parse_stream(
+            &ClaudeProvider,
+            &[
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#,
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":" never"}}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello");
+        let error = parsed.error.expect("error event");
+        assert_eq!(error.kind, ErrorKind::Server);
+        assert_eq!(error.code, "overloaded_error");
+        assert_eq!(error.message, "Overloaded");
+        assert!(error.retryable);
+    }
+
+    #[test]
+    fn maps_stop_reasons() {
+        assert_eq!(map_stop_reason("end_turn"), FinishReason::Stop);
+        assert_eq!(map_stop_reason("stop_sequence"), FinishReason::Stop);
+        assert_eq!(map_stop_reason("refusal"), FinishReason::ContentFilter);
+        assert_eq!(map_stop_reason("pause_turn"), FinishReason::Other);
+    }
+}
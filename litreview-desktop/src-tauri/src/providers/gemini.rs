@@ -0,0 +1,328 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+use super::{
+    json_u32, parse_json_body, FinishReason, LlmProvider, ModelInfo, ParsedChunk, TokenUsage,
+};
+use crate::error::AppError;
+use crate::{ChatRole, StreamRequestConfig};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    parts: Option<Vec<GeminiPart>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiPart {
+    text: Option<String>,
+    /// The text is a thought summary rather than part of the answer
+    #[serde(default)]
+    thought: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiStreamResponse {
+    candidates: Option<Vec<GeminiCandidate>>,
+    usage_metadata: Option<GeminiUsageMetadata>,
+    /// Sent in place of a chunk when generation fails mid-stream
+    error: Option<serde_json::Value>,
+}
+
+fn map_finish_reason(reason: &str) -> FinishReason {
+    match reason {
+        "STOP" => FinishReason::Stop,
+        "MAX_TOKENS" => FinishReason::Length,
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+            FinishReason::ContentFilter
+        }
+        _ => FinishReason::Other,
+    }
+}
+
+/// Every streamed chunk is a complete `GenerateContentResponse`
+fn chunk_from_response(response: GeminiStreamResponse) -> ParsedChunk {
+    let mut chunk = ParsedChunk::default();
+
+    if let Some(error) = response.error {
+        chunk.error = Some(AppError::stream(&error));
+        chunk.done = true;
+        return chunk;
+    }
+
+    for candidate in response.candidates.unwrap_or_default() {
+        let parts = candidate
+            .content
+            .and_then(|content| content.parts)
+            .unwrap_or_default();
+        for part in parts {
+            if let Some(text) = part.text.filter(|text| !text.is_empty()) {
+                if part.thought {
+                    chunk.reasoning.push(text);
+                } else {
+                    chunk.deltas.push(text);
+                }
+            }
+        }
+        if let Some(reason) = candidate.finish_reason {
+            chunk.finish_reason = Some(map_finish_reason(&reason));
+        }
+    }
+
+    chunk.usage = response.usage_metadata.map(|usage| TokenUsage {
+        input_tokens: usage.prompt_token_count,
+        output_tokens: usage.candidates_token_count,
+    });
+
+    chunk
+}
+
+/// Google Gemini API
+pub struct GeminiProvider;
+
+impl GeminiProvider {
+    fn post(
+        client: &Client,
+        config: &StreamRequestConfig,
+        action: &str,
+        body: serde_json::Value,
+    ) -> RequestBuilder {
+        let url = format!(
+            "{}/v1beta/models/{}:{}?key={}",
+            config.base_url.trim_end_matches('/'),
+            config.model,
+            action,
+            config.api_key
+        );
+
+        client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&body)
+    }
+}
+
+impl LlmProvider for GeminiProvider {
+    fn provider_type(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn build_stream_request(
+        &self,
+        client: &Client,
+        config: &StreamRequestConfig,
+    ) -> RequestBuilder {
+        // Gemini calls the assistant role "model"
+        let contents: Vec<serde_json::Value> = config
+            .conversation()
+            .into_iter()
+            .map(|message| {
+                let role = match message.role {
+                    ChatRole::Assistant => "model",
+                    _ => "user",
+                };
+                serde_json::json!({"role": role, "parts": [{"text": message.content}]})
+            })
+            .collect();
+
+        let params = &config.params;
+        let mut generation_config = serde_json::json!({});
+        if let Some(temperature) = params.temperature {
+            generation_config["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(max_tokens) = params.max_tokens {
+            generation_config["maxOutputTokens"] = serde_json::json!(max_tokens);
+        }
+        if let Some(top_p) = params.top_p {
+            generation_config["topP"] = serde_json::json!(top_p);
+        }
+        if let Some(stop) = &params.stop_sequences {
+            generation_config["stopSequences"] = serde_json::json!(stop);
+        }
+        // Thought summaries are only returned when asked for
+        if let Some(budget) = config.thinking_budget {
+            generation_config["thinkingConfig"] =
+                serde_json::json!({"thinkingBudget": budget, "includeThoughts": true});
+        }
+
+        // Build body with optional system instruction
+        let mut body = serde_json::json!({
+            "contents": contents,
+            "generationConfig": generation_config
+        });
+
+        // Add system instruction if provided
+        if let Some(system) = config.system_text() {
+            body["systemInstruction"] = serde_json::json!({
+                "parts": [{"text": system}]
+            });
+        }
+
+        Self::post(client, config, "streamGenerateContent", body).query(&[("alt", "sse")])
+    }
+
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let body = serde_json::json!({
+            "contents": [{
+                "parts": [{"text": "ping"}]
+            }],
+            "generationConfig": {
+                "maxOutputTokens": 1
+            }
+        });
+
+        Self::post(client, config, "generateContent", body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk {
+        serde_json::from_str::<GeminiStreamResponse>(data)
+            .map(chunk_from_response)
+            .unwrap_or_default()
+    }
+
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String> {
+        let json = parse_json_body(body)?;
+
+        // Unknown shapes yield no text, so the caller falls back to the raw body
+        let mut chunk = serde_json::from_value::<GeminiStreamResponse>(json)
+            .map(chunk_from_response)
+            .unwrap_or_default();
+        chunk.done = true;
+
+        Ok(chunk)
+    }
+
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let url = format!("{}/v1beta/models", config.base_url.trim_end_matches('/'));
+
+        client
+            .get(&url)
+            .query(&[("key", config.api_key.as_str()), ("pageSize", "1000")])
+    }
+
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String> {
+        let json = parse_json_body(body)?;
+        let entries = json
+            .get("models")
+            .and_then(|models| models.as_array())
+            .ok_or("Unexpected model list response")?;
+
+        let mut models: Vec<ModelInfo> = entries
+            .iter()
+            .filter_map(|entry| {
+                // Names are "models/<id>", requests take the bare id
+                let name = entry.get("name")?.as_str()?;
+                Some(ModelInfo {
+                    id: name.strip_prefix("models/").unwrap_or(name).to_string(),
+                    display_name: entry
+                        .get("displayName")
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string),
+                    context_window: json_u32(entry, "inputTokenLimit"),
+                    max_output_tokens: json_u32(entry, "outputTokenLimit"),
+                    capabilities: entry
+                        .get("supportedGenerationMethods")
+                        .and_then(|methods| methods.as_array())
+                        .map(|methods| {
+                            methods
+                                .iter()
+                                .filter_map(|method| method.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::providers::test_support::parse_stream;
+
+    #[test]
+    fn keeps_latest_cumulative_usage() {
+        let parsed = parse_stream(
+            &GeminiProvider,
+            &[
+                r#"{"candidates":[{"content":{"parts":[{"text":"第一"}],"role":"model"}}],
+                    "usageMetadata":{"promptTokenCount":8,"candidatesTokenCount":2}}"#,
+                r#"{"candidates":[{"content":{"parts":[{"text":"部分"}],"role":"model"},"finishReason":"STOP"}],
+                    "usageMetadata":{"promptTokenCount":8,"candidatesTokenCount":5}}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "第一部分");
+        // Gemini has no end marker; the stream simply closes
+        assert!(!parsed.done);
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            parsed.summary.usage,
+            Some(TokenUsage {
+                input_tokens: 8,
+                output_tokens: 5
+            })
+        );
+    }
+
+    #[test]
+    fn separates_thought_summaries_from_text() {
+        let parsed = parse_stream(
+            &GeminiProvider,
+            &[
+                r#"{"candidates":[{"content":{"parts":[{"text":"Weighing sources","thought":true}],"role":"model"}}]}"#,
+                r#"{"candidates":[{"content":{"parts":[{"text":"Answer"}],"role":"model"}}]}"#,
+            ],
+        );
+
+        assert_eq!(parsed.reasoning, "Weighing sources");
+        assert_eq!(parsed.text, "Answer");
+    }
+
+    #[test]
+    fn stops_at_error_chunk() {
+        let parsed = parse_stream(
+            &GeminiProvider,
+            &[
+                r#"{"candidates":[{"content":{"parts":[{"text":"Hello"}],"role":"model"}}]}"#,
+                r#"{"error":{"code":429,"message":"Resource has been exhausted","status":"RESOURCE_EXHAUSTED"}}"#,
+                r#"{"candidates":[{"content":{"parts":[{"text":" never"}],"role":"model"}}]}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello");
+        let error = parsed.error.expect("error chunk");
+        assert_eq!(error.kind, ErrorKind::Quota);
+        assert_eq!(error.code, "RESOURCE_EXHAUSTED");
+        assert_eq!(error.message, "Resource has been exhausted");
+        assert!(error.retryable);
+    }
+
+    #[test]
+    fn maps_finish_reasons() {
+        assert_eq!(map_finish_reason("MAX_TOKENS"), FinishReason::Length);
+        assert_eq!(map_finish_reason("SAFETY"), FinishReason::ContentFilter);
+        assert_eq!(map_finish_reason("OTHER"), FinishReason::Other);
+    }
+}
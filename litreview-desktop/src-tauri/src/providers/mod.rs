@@ -0,0 +1,240 @@
+//! LLM provider abstraction.
+//!
+//! Each supported API implements [`LlmProvider`], which knows how to build its
+//! HTTP requests and how to parse its responses. The network I/O and event
+//! emission live in `lib.rs`, so streaming, connection testing and any future
+//! feature go through the same per-provider implementation.
+
+mod azure;
+mod claude;
+mod gemini;
+mod ollama;
+mod openai;
+mod responses;
+
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::StreamRequestConfig;
+
+pub use azure::AzureOpenAiProvider;
+pub use claude::ClaudeProvider;
+pub use gemini::GeminiProvider;
+pub use ollama::{OllamaOptions, OllamaProvider};
+pub use openai::{OpenAiApi, OpenAiProvider};
+
+/// Result of parsing one `data:` payload from a provider's SSE stream
+#[derive(Debug, Default)]
+pub struct ParsedChunk {
+    /// Text deltas to forward to the frontend, in order
+    pub deltas: Vec<String>,
+    /// Reasoning ("thinking") deltas, which precede the text of the same chunk
+    pub reasoning: Vec<String>,
+    /// The provider signalled the end of the generation
+    pub done: bool,
+    /// Token counts reported in this chunk, possibly partial
+    pub usage: Option<TokenUsage>,
+    pub finish_reason: Option<FinishReason>,
+    /// The provider reported a failure inside an otherwise successful stream
+    pub error: Option<AppError>,
+}
+
+/// Token counts for a completed generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl TokenUsage {
+    /// Overwrite fields that `other` reports
+    ///
+    /// Providers spread counts over several events (Claude sends input tokens
+    /// in `message_start` and output tokens in `message_delta`), and the later
+    /// value is always the cumulative one.
+    pub fn merge(&mut self, other: TokenUsage) {
+        if other.input_tokens > 0 {
+            self.input_tokens = other.input_tokens;
+        }
+        if other.output_tokens > 0 {
+            self.output_tokens = other.output_tokens;
+        }
+    }
+}
+
+/// Why generation stopped, normalized across providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Natural end of the answer or a stop sequence
+    Stop,
+    /// Output was truncated by the token limit
+    Length,
+    /// Blocked by the provider's safety filters
+    ContentFilter,
+    ToolUse,
+    Other,
+}
+
+/// Usage and finish reason accumulated over a whole stream
+#[derive(Debug, Clone, Default)]
+pub struct StreamSummary {
+    pub usage: Option<TokenUsage>,
+    pub finish_reason: Option<FinishReason>,
+}
+
+impl StreamSummary {
+    pub fn record(&mut self, chunk: &ParsedChunk) {
+        if let Some(usage) = chunk.usage {
+            self.usage
+                .get_or_insert_with(TokenUsage::default)
+                .merge(usage);
+        }
+        if chunk.finish_reason.is_some() {
+            self.finish_reason = chunk.finish_reason;
+        }
+    }
+}
+
+/// A model offered by a provider, as reported by its model list endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Maximum input tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Provider-specific feature names, e.g. Gemini's "generateContent"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
+}
+
+pub trait LlmProvider: Send + Sync {
+    /// The `provider_type` value this implementation is registered under
+    fn provider_type(&self) -> &'static str;
+
+    /// Build the streaming generation request
+    fn build_stream_request(&self, client: &Client, config: &StreamRequestConfig)
+        -> RequestBuilder;
+
+    /// Build a minimal non-streaming request used to verify connectivity
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder;
+
+    /// Parse one streamed payload: an SSE `data:` field or an NDJSON line
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk;
+
+    /// Parse a complete non-streaming JSON response body
+    ///
+    /// Some gateways ignore `stream: true` and answer with a plain JSON body.
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String>;
+
+    /// Build the request listing the models available to the account
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder;
+
+    /// Parse the response of `build_list_models`, sorted by id
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String>;
+}
+
+/// Provider implementations keyed by `ProviderConfig.provider_type`
+pub struct ProviderRegistry {
+    providers: HashMap<&'static str, Arc<dyn LlmProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn empty() -> Self {
+        Self {
+            providers: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, provider: Arc<dyn LlmProvider>) {
+        self.providers.insert(provider.provider_type(), provider);
+    }
+
+    pub fn get(&self, provider_type: &str) -> Option<Arc<dyn LlmProvider>> {
+        self.providers.get(provider_type).cloned()
+    }
+
+    /// Registered provider types, sorted
+    pub fn provider_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<_> = self.providers.keys().copied().collect();
+        types.sort_unstable();
+        types
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Arc::new(OpenAiProvider));
+        registry.register(Arc::new(ClaudeProvider));
+        registry.register(Arc::new(GeminiProvider));
+        registry.register(Arc::new(OllamaProvider));
+        registry.register(Arc::new(AzureOpenAiProvider));
+        registry
+    }
+}
+
+/// Parse a JSON body and turn an `error` object into an `Err`
+fn parse_json_body(body_text: &str) -> Result<serde_json::Value, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(body_text).map_err(|e| format!("Invalid JSON response: {}", e))?;
+
+    if let Some(error) = json.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .or_else(|| error.as_str())
+            .unwrap_or("Unknown error");
+        return Err(format!("API error: {}", message));
+    }
+
+    Ok(json)
+}
+
+/// Read an optional token limit that may be missing, null or negative
+fn json_u32(value: &serde_json::Value, key: &str) -> Option<u32> {
+    value
+        .get(key)
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+}
+
+/// Fixtures for the providers' parser tests
+#[cfg(test)]
+mod test_support {
+    use super::*;
+
+    /// What the stream loop in `lib.rs` would make of a sequence of payloads
+    #[derive(Debug, Default)]
+    pub struct Parsed {
+        pub text: String,
+        pub reasoning: String,
+        pub summary: StreamSummary,
+        pub done: bool,
+        pub error: Option<AppError>,
+    }
+
+    /// Parse `payloads` in order, stopping at an error or the end marker
+    pub fn parse_stream(provider: &dyn LlmProvider, payloads: &[&str]) -> Parsed {
+        let mut parsed = Parsed::default();
+        for payload in payloads {
+            let chunk = provider.parse_stream_data(payload);
+            parsed.summary.record(&chunk);
+            parsed.reasoning.extend(chunk.reasoning);
+            parsed.text.extend(chunk.deltas);
+            if chunk.error.is_some() || chunk.done {
+                parsed.error = chunk.error;
+                parsed.done = true;
+                break;
+            }
+        }
+        parsed
+    }
+}
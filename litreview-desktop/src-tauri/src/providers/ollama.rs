@@ -0,0 +1,341 @@
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::{parse_json_body, FinishReason, LlmProvider, ModelInfo, ParsedChunk, TokenUsage};
+use crate::error::AppError;
+use crate::StreamRequestConfig;
+
+/// Ollama-specific settings, `[providers.<name>.ollama]` in config.toml
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OllamaOptions {
+    /// Context length in tokens. Ollama's default is a few thousand tokens and
+    /// silently drops the start of longer prompts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// How long the model stays loaded after a request, e.g. "10m"; a
+    /// negative duration keeps it loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+    /// Send the conversation as plain text to `/api/generate` without
+    /// applying the model's prompt template
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub raw: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+    /// Reasoning of thinking models, separate from `content` since Ollama 0.9
+    thinking: Option<String>,
+}
+
+/// One NDJSON line of `/api/chat` or `/api/generate`, or a complete response
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    /// `/api/chat`
+    message: Option<OllamaMessage>,
+    /// `/api/generate`
+    response: Option<String>,
+    /// `/api/generate` reasoning
+    thinking: Option<String>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    /// A failure after the response started, e.g. the model runner crashed
+    error: Option<serde_json::Value>,
+}
+
+fn map_finish_reason(reason: &str) -> FinishReason {
+    match reason {
+        "stop" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        _ => FinishReason::Other,
+    }
+}
+
+fn chunk_from_response(response: OllamaResponse) -> ParsedChunk {
+    let mut chunk = ParsedChunk {
+        done: response.done,
+        ..Default::default()
+    };
+    if let Some(error) = &response.error {
+        chunk.error = Some(AppError::stream(error));
+        chunk.done = true;
+        return chunk;
+    }
+
+    let (text, thinking) = match response.message {
+        Some(message) => (message.content, message.thinking),
+        None => (response.response.unwrap_or_default(), response.thinking),
+    };
+    if let Some(thinking) = thinking.filter(|text| !text.is_empty()) {
+        chunk.reasoning.push(thinking);
+    }
+    if !text.is_empty() {
+        chunk.deltas.push(text);
+    }
+
+    // Counts and reason are only sent on the final line
+    if response.done {
+        chunk.finish_reason = response.done_reason.as_deref().map(map_finish_reason);
+        if response.prompt_eval_count.is_some() || response.eval_count.is_some() {
+            chunk.usage = Some(TokenUsage {
+                input_tokens: response.prompt_eval_count.unwrap_or(0),
+                output_tokens: response.eval_count.unwrap_or(0),
+            });
+        }
+    }
+
+    chunk
+}
+
+/// Native Ollama API, streamed as NDJSON
+pub struct OllamaProvider;
+
+impl OllamaProvider {
+    fn url(config: &StreamRequestConfig, path: &str) -> String {
+        format!("{}{}", config.base_url.trim_end_matches('/'), path)
+    }
+
+    /// Ollama itself has no auth, but may sit behind a proxy that does
+    fn authorize(request: RequestBuilder, config: &StreamRequestConfig) -> RequestBuilder {
+        if config.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", config.api_key))
+        }
+    }
+
+    /// The `options` object: sampling parameters plus `num_ctx`
+    fn options(config: &StreamRequestConfig) -> serde_json::Value {
+        let params = &config.params;
+        let mut options = serde_json::json!({});
+        if let Some(temperature) = params.temperature {
+            options["temperature"] = serde_json::json!(temperature);
+        }
+
+        if let Some(max_tokens) = params.max_tokens {
+            options["num_predict"] = serde_json::json!(max_tokens);
+        }
+        if let Some(top_p) = params.top_p {
+            options["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(stop) = &params.stop_sequences {
+            options["stop"] = serde_json::json!(stop);
+        }
+        if let Some(num_ctx) = config.ollama.as_ref().and_then(|ollama| ollama.num_ctx) {
+            options["num_ctx"] = serde_json::json!(num_ctx);
+        }
+
+        options
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn provider_type(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn build_stream_request(
+        &self,
+        client: &Client,
+        config: &StreamRequestConfig,
+    ) -> RequestBuilder {
+        let ollama = config.ollama.clone().unwrap_or_default();
+
+        let mut body = if ollama.raw {
+            // Raw mode takes a single prompt that is already in the model's format
+            let prompt: Vec<String> = config
+                .system_text()
+                .into_iter()
+                .chain(config.conversation().into_iter().map(|m| m.content))
+                .collect();
+            serde_json::json!({
+                "model": config.model,
+                "prompt": prompt.join("\n\n"),
+                "raw": true
+            })
+        } else {
+            let mut messages = Vec::new();
+            if let Some(system) = config.system_text() {
+                messages.push(serde_json::json!({"role": "system", "content": system}));
+            }
+            for message in config.conversation() {
+                messages
+                    .push(serde_json::json!({"role": message.role, "content": message.content}));
+            }
+            serde_json::json!({
+                "model": config.model,
+                "messages": messages
+            })
+        };
+
+        body["stream"] = serde_json::json!(true);
+        body["options"] = Self::options(config);
+        if let Some(keep_alive) = &ollama.keep_alive {
+            body["keep_alive"] = serde_json::json!(keep_alive);
+        }
+
+        let path = if ollama.raw {
+            "/api/generate"
+        } else {
+            "/api/chat"
+        };
+        Self::authorize(client.post(Self::url(config, path)), config).json(&body)
+    }
+
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let body = serde_json::json!({
+            "model": config.model,
+            "messages": [{"role": "user", "content": "ping"}],
+            "stream": false,
+            "options": {"num_predict": 1, "temperature": 0.0}
+        });
+
+        Self::authorize(client.post(Self::url(config, "/api/chat")), config).json(&body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk {
+        serde_json::from_str::<OllamaResponse>(data)
+            .map(chunk_from_response)
+            .unwrap_or_default()
+    }
+
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String> {
+        let json = parse_json_body(body)?;
+
+        // Unknown shapes yield no text, so the caller falls back to the raw body
+        let mut chunk = serde_json::from_value::<OllamaResponse>(json)
+            .map(chunk_from_response)
+            .unwrap_or_default();
+        chunk.done = true;
+
+        Ok(chunk)
+    }
+
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        Self::authorize(client.get(Self::url(config, "/api/tags")), config)
+    }
+
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String> {
+        let json = parse_json_body(body)?;
+        let entries = json
+            .get("models")
+            .and_then(|models| models.as_array())
+            .ok_or("Unexpected model list response")?;
+
+        // Only locally pulled models are listed
+        let mut models: Vec<ModelInfo> = entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.get("name")?.as_str()?;
+                let size = entry
+                    .get("details")
+                    .and_then(|details| details.get("parameter_size"))
+                    .and_then(|size| size.as_str())
+                    .filter(|size| !size.is_empty());
+                Some(ModelInfo {
+                    id: name.to_string(),
+                    display_name: size.map(|size| format!("{} ({})", name, size)),
+                    context_window: None,
+                    max_output_tokens: None,
+                    capabilities: Vec::new(),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::test_support::parse_stream;
+
+    #[test]
+    fn reads_usage_and_reason_from_final_line() {
+        let parsed = parse_stream(
+            &OllamaProvider,
+            &[
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"文献"},"done":false}"#,
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"综述"},"done":false}"#,
+                r#"{"model":"qwen3","message":{"role":"assistant","content":""},"done":true,
+                    "done_reason":"length","prompt_eval_count":26,"eval_count":2}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "文献综述");
+        assert!(parsed.done);
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Length));
+        assert_eq!(
+            parsed.summary.usage,
+            Some(TokenUsage {
+                input_tokens: 26,
+                output_tokens: 2
+            })
+        );
+    }
+
+    #[test]
+    fn parses_generate_lines() {
+        let parsed = parse_stream(
+            &OllamaProvider,
+            &[
+                r#"{"model":"llama3","response":"Hi","done":false}"#,
+                r#"{"model":"llama3","response":"","done":true,"done_reason":"stop","eval_count":1}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hi");
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            parsed.summary.usage.map(|usage| usage.input_tokens),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn separates_thinking_from_content() {
+        let parsed = parse_stream(
+            &OllamaProvider,
+            &[
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"","thinking":"Okay, "},"done":false}"#,
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"","thinking":"the user"},"done":false}"#,
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"Answer"},"done":false}"#,
+                r#"{"model":"qwen3","message":{"role":"assistant","content":""},"done":true,"done_reason":"stop"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.reasoning, "Okay, the user");
+        assert_eq!(parsed.text, "Answer");
+    }
+
+    #[test]
+    fn stops_at_error_line() {
+        let parsed = parse_stream(
+            &OllamaProvider,
+            &[
+                r#"{"model":"qwen3","message":{"role":"assistant","content":"Hello"},"done":false}"#,
+                r#"{"error":"model runner has unexpectedly stopped"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello");
+        let error = parsed.error.expect("error line");
+        assert_eq!(error.code, "stream_error");
+        assert_eq!(error.message, "model runner has unexpectedly stopped");
+        assert!(!error.retryable);
+    }
+
+    #[test]
+    fn maps_done_reasons() {
+        assert_eq!(map_finish_reason("stop"), FinishReason::Stop);
+        assert_eq!(map_finish_reason("length"), FinishReason::Length);
+        assert_eq!(map_finish_reason("load"), FinishReason::Other);
+    }
+}
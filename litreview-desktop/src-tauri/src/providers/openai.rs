@@ -0,0 +1,412 @@
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    json_u32, parse_json_body, responses, FinishReason, LlmProvider, ModelInfo, ParsedChunk,
+    TokenUsage,
+};
+use crate::error::AppError;
+use crate::StreamRequestConfig;
+
+/// Endpoint generation requests of an "openai" provider go to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAiApi {
+    /// `/chat/completions`, supported by every compatible server
+    #[default]
+    ChatCompletions,
+    /// `/responses`, preferred by OpenAI's reasoning models
+    Responses,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIChoice {
+    delta: Option<OpenAIDelta>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIDelta {
+    content: Option<String>,
+    /// DeepSeek-R1 and servers that copy its format
+    reasoning_content: Option<String>,
+    /// OpenRouter and vLLM
+    reasoning: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+impl From<OpenAIUsage> for TokenUsage {
+    fn from(usage: OpenAIUsage) -> Self {
+        TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamResponse {
+    // The final usage chunk has an empty (or, on some servers, missing) choices array
+    #[serde(default)]
+    choices: Vec<OpenAIChoice>,
+    usage: Option<OpenAIUsage>,
+}
+
+fn map_finish_reason(reason: &str) -> FinishReason {
+    match reason {
+        "stop" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        "content_filter" => FinishReason::ContentFilter,
+        "tool_calls" | "function_call" => FinishReason::ToolUse,
+        _ => FinishReason::Other,
+    }
+}
+
+/// Streaming chat completions body, shared with Azure OpenAI
+pub(super) fn stream_body(config: &StreamRequestConfig) -> serde_json::Value {
+    // Build messages array with optional system prompt
+    let mut messages = Vec::new();
+    if let Some(system) = config.system_text() {
+        messages.push(serde_json::json!({"role": "system", "content": system}));
+    }
+    for message in config.conversation() {
+        messages.push(serde_json::json!({"role": message.role, "content": message.content}));
+    }
+
+    let params = &config.params;
+    let mut body = serde_json::json!({
+        "model": config.model,
+        "messages": messages,
+        "stream": true,
+        "stream_options": {"include_usage": true}
+    });
+
+    if let Some(temperature) = params.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        body["max_tokens"] = serde_json::json!(max_tokens);
+    }
+    if let Some(top_p) = params.top_p {
+        body["top_p"] = serde_json::json!(top_p);
+    }
+    if let Some(stop) = &params.stop_sequences {
+        body["stop"] = serde_json::json!(stop);
+    }
+
+    body
+}
+
+/// Minimal non-streaming chat completions body
+///
+/// Neither `temperature` nor a default `max_tokens` is sent: o-series and
+/// gpt-5 models reject both.
+pub(super) fn health_check_body(config: &StreamRequestConfig) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "model": config.model,
+        "messages": [{"role": "user", "content": "ping"}]
+    });
+    if let Some(max_tokens) = config.params.max_tokens {
+        body["max_tokens"] = serde_json::json!(max_tokens);
+    }
+
+    body
+}
+
+/// OpenAI-compatible API (OpenAI, Ollama, DeepSeek, Moonshot, etc.)
+pub struct OpenAiProvider;
+
+impl OpenAiProvider {
+    fn post(
+        client: &Client,
+        config: &StreamRequestConfig,
+        body: serde_json::Value,
+    ) -> RequestBuilder {
+        let path = match config.openai_api.unwrap_or_default() {
+            OpenAiApi::ChatCompletions => "chat/completions",
+            OpenAiApi::Responses => "responses",
+        };
+        let url = format!("{}/{}", config.base_url.trim_end_matches('/'), path);
+
+        let request = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&body);
+
+        // Add auth header if api_key is provided (Ollama may not need it)
+        if config.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", config.api_key))
+        }
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn provider_type(&self) -> &'static str {
+        "openai"
+    }
+
+    fn build_stream_request(
+        &self,
+        client: &Client,
+        config: &StreamRequestConfig,
+    ) -> RequestBuilder {
+        let body = match config.openai_api.unwrap_or_default() {
+            OpenAiApi::ChatCompletions => stream_body(config),
+            OpenAiApi::Responses => responses::stream_body(config),
+        };
+        Self::post(client, config, body)
+    }
+
+    fn build_health_check(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let body = match config.openai_api.unwrap_or_default() {
+            OpenAiApi::ChatCompletions => health_check_body(config),
+            OpenAiApi::Responses => responses::health_check_body(config),
+        };
+        Self::post(client, config, body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> ParsedChunk {
+        let mut chunk = ParsedChunk::default();
+
+        if data == "[DONE]" {
+            chunk.done = true;
+            return chunk;
+        }
+
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(data) else {
+            return chunk;
+        };
+        if responses::is_event(&json) {
+            return responses::parse_event(json);
+        }
+        // Some compatible servers report a failure as a chunk of its own
+        if let Some(error) = json.get("error") {
+            chunk.error = Some(AppError::stream(error));
+            chunk.done = true;
+            return chunk;
+        }
+
+        // The finish_reason chunk is not the last one: with `include_usage` the
+        // token counts follow in a separate chunk, so only `[DONE]` ends the stream
+        if let Ok(parsed) = serde_json::from_value::<OpenAIStreamResponse>(json) {
+            for choice in parsed.choices {
+                if let Some(delta) = choice.delta {
+                    if let Some(reasoning) = delta
+                        .reasoning_content
+                        .or(delta.reasoning)
+                        .filter(|text| !text.is_empty())
+                    {
+                        chunk.reasoning.push(reasoning);
+                    }
+                    if let Some(content) = delta.content.filter(|text| !text.is_empty()) {
+                        chunk.deltas.push(content);
+                    }
+                }
+                if let Some(reason) = choice.finish_reason {
+                    chunk.finish_reason = Some(map_finish_reason(&reason));
+                }
+            }
+            chunk.usage = parsed.usage.map(TokenUsage::from);
+        }
+
+        chunk
+    }
+
+    fn parse_response_body(&self, body: &str) -> Result<ParsedChunk, String> {
+        let json = parse_json_body(body)?;
+        if responses::is_response(&json) {
+            return responses::parse_response(json);
+        }
+
+        let mut chunk = ParsedChunk {
+            done: true,
+            ..Default::default()
+        };
+
+        if let Some(choices) = json.get("choices").and_then(|c| c.as_array()) {
+            for choice in choices {
+                if let Some(reasoning) = choice
+                    .get("message")
+                    .and_then(|message| {
+                        message
+                            .get("reasoning_content")
+                            .or_else(|| message.get("reasoning"))
+                    })
+                    .and_then(|reasoning| reasoning.as_str())
+                {
+                    chunk.reasoning.push(reasoning.to_string());
+                }
+                if let Some(message_content) = choice
+                    .get("message")
+                    .and_then(|message| message.get("content"))
+                    .and_then(|content| content.as_str())
+                {
+                    chunk.deltas.push(message_content.to_string());
+                } else if let Some(text) = choice.get("text").and_then(|text| text.as_str()) {
+                    chunk.deltas.push(text.to_string());
+                }
+                if let Some(reason) = choice.get("finish_reason").and_then(|r| r.as_str()) {
+                    chunk.finish_reason = Some(map_finish_reason(reason));
+                }
+            }
+        }
+
+        if chunk.deltas.is_empty() {
+            if let Some(result) = json
+                .get("result")
+                .and_then(|value| value.get("response"))
+                .and_then(|value| value.as_str())
+            {
+                chunk.deltas.push(result.to_string());
+            }
+        }
+
+        chunk.usage = json
+            .get("usage")
+            .cloned()
+            .and_then(|usage| serde_json::from_value::<OpenAIUsage>(usage).ok())
+            .map(TokenUsage::from);
+
+        Ok(chunk)
+    }
+
+    fn build_list_models(&self, client: &Client, config: &StreamRequestConfig) -> RequestBuilder {
+        let url = format!("{}/models", config.base_url.trim_end_matches('/'));
+
+        let request = client.get(&url);
+        if config.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", config.api_key))
+        }
+    }
+
+    fn parse_models(&self, body: &str) -> Result<Vec<ModelInfo>, String> {
+        let json = parse_json_body(body)?;
+        let entries = json
+            .get("data")
+            .and_then(|data| data.as_array())
+            .ok_or("Unexpected model list response")?;
+
+        // OpenAI only reports ids; gateways such as OpenRouter add limits
+        let mut models: Vec<ModelInfo> = entries
+            .iter()
+            .filter_map(|entry| {
+                Some(ModelInfo {
+                    id: entry.get("id")?.as_str()?.to_string(),
+                    display_name: entry
+                        .get("name")
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string),
+                    context_window: json_u32(entry, "context_length")
+                        .or_else(|| json_u32(entry, "context_window")),
+                    max_output_tokens: None,
+                    capabilities: Vec::new(),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::providers::test_support::parse_stream;
+
+    #[test]
+    fn streams_text_then_usage_after_finish_reason() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{"content":"文献"}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{"content":"综述"},"finish_reason":"length"}]}"#,
+                r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":34}}"#,
+                "[DONE]",
+            ],
+        );
+
+        assert_eq!(parsed.text, "文献综述");
+        assert!(parsed.done);
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Length));
+        assert_eq!(
+            parsed.summary.usage,
+            Some(TokenUsage {
+                input_tokens: 12,
+                output_tokens: 34
+            })
+        );
+    }
+
+    #[test]
+    fn separates_reasoning_from_content() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"choices":[{"index":0,"delta":{"role":"assistant","reasoning_content":"先检索","content":null}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{"reasoning":"再归纳"}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{"reasoning_content":"","content":"结论"}}]}"#,
+                "[DONE]",
+            ],
+        );
+
+        assert_eq!(parsed.reasoning, "先检索再归纳");
+        assert_eq!(parsed.text, "结论");
+    }
+
+    #[test]
+    fn stops_at_error_chunk() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"choices":[{"index":0,"delta":{"content":"Hello"}}]}"#,
+                r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#,
+                "[DONE]",
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello");
+        let error = parsed.error.expect("error chunk");
+        assert_eq!(error.kind, ErrorKind::Quota);
+        assert_eq!(error.code, "rate_limit_exceeded");
+        assert!(error.retryable);
+    }
+
+    #[test]
+    fn maps_finish_reasons() {
+        assert_eq!(map_finish_reason("stop"), FinishReason::Stop);
+        assert_eq!(
+            map_finish_reason("content_filter"),
+            FinishReason::ContentFilter
+        );
+        assert_eq!(map_finish_reason("tool_calls"), FinishReason::ToolUse);
+        assert_eq!(map_finish_reason("eos"), FinishReason::Other);
+    }
+
+    #[test]
+    fn parses_plain_response_body() {
+        let chunk = OpenAiProvider
+            .parse_response_body(
+                r#"{"choices":[{"message":{"content":"OK"},"finish_reason":"stop"}],
+                    "usage":{"prompt_tokens":5,"completion_tokens":1}}"#,
+            )
+            .unwrap();
+
+        assert_eq!(chunk.deltas, vec!["OK"]);
+        assert_eq!(chunk.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(chunk.usage.map(|usage| usage.output_tokens), Some(1));
+    }
+}
@@ -0,0 +1,346 @@
+//! OpenAI Responses API (`/responses`), selected with `openai_api = "responses"`.
+//!
+//! Every stream payload carries a `type` such as `response.output_text.delta`,
+//! which chat completion chunks never do, so the OpenAI provider tells the two
+//! apart by content.
+
+use serde::Deserialize;
+
+use super::{FinishReason, ParsedChunk, TokenUsage};
+use crate::error::AppError;
+use crate::StreamRequestConfig;
+
+/// The Responses API rejects smaller limits
+const MIN_OUTPUT_TOKENS: u32 = 16;
+
+#[derive(Debug, Deserialize)]
+struct ResponsesEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    /// `response.output_text.delta` and `response.reasoning_summary_text.delta`
+    delta: Option<String>,
+    /// `response.completed`, `response.incomplete` and `response.failed`
+    response: Option<ResponseObject>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ResponseObject {
+    status: Option<String>,
+    #[serde(default)]
+    output: Vec<OutputItem>,
+    usage: Option<ResponsesUsage>,
+    incomplete_details: Option<IncompleteDetails>,
+    /// `{"code": ..., "message": ...}` of a failed response
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    /// Parts of a "message" item
+    #[serde(default)]
+    content: Vec<OutputContent>,
+    /// Parts of a "reasoning" item
+    #[serde(default)]
+    summary: Vec<OutputContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct IncompleteDetails {
+    reason: Option<String>,
+}
+
+/// o-series and gpt-5 models, which only stream their reasoning summary when
+/// asked for it and reject the `reasoning` option otherwise
+///
+/// gpt-5 chat variants are plain chat models.
+fn is_reasoning_model(model: &str) -> bool {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    let o_series = model.starts_with('o') && model[1..].starts_with(|c: char| c.is_ascii_digit());
+    (o_series || model.starts_with("gpt-5")) && !model.contains("-chat")
+}
+
+/// Input items and options shared by the streamed and health check requests
+fn base_body(config: &StreamRequestConfig) -> serde_json::Value {
+    let input: Vec<serde_json::Value> = config
+        .conversation()
+        .into_iter()
+        .map(|message| serde_json::json!({"role": message.role, "content": message.content}))
+        .collect();
+
+    let mut body = serde_json::json!({
+        "model": config.model,
+        "input": input,
+        // Nothing is read back later, so keep the conversation off the server
+        "store": false
+    });
+    if let Some(system) = config.system_text() {
+        body["instructions"] = serde_json::json!(system);
+    }
+
+    body
+}
+
+/// Streaming request body
+///
+/// Sampling parameters are only sent when set: reasoning models reject
+/// `temperature` and `top_p`. There is no equivalent of `stop`.
+pub(super) fn stream_body(config: &StreamRequestConfig) -> serde_json::Value {
+    let params = &config.params;
+    let mut body = base_body(config);
+    body["stream"] = serde_json::json!(true);
+
+    if is_reasoning_model(&config.model) {
+        body["reasoning"] = serde_json::json!({"summary": "auto"});
+    }
+
+    if let Some(temperature) = params.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    if let Some(top_p) = params.top_p {
+        body["top_p"] = serde_json::json!(top_p);
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        body["max_output_tokens"] = serde_json::json!(max_tokens.max(MIN_OUTPUT_TOKENS));
+    }
+
+    body
+}
+
+pub(super) fn health_check_body(config: &StreamRequestConfig) -> serde_json::Value {
+    serde_json::json!({
+        "model": config.model,
+        "input": "ping",
+        "max_output_tokens": MIN_OUTPUT_TOKENS,
+        "store": false
+    })
+}
+
+/// Whether a stream payload is a Responses API event
+pub(super) fn is_event(json: &serde_json::Value) -> bool {
+    json.get("type").is_some_and(|t| t.is_string())
+}
+
+/// Whether a complete body is a Responses API response object
+pub(super) fn is_response(json: &serde_json::Value) -> bool {
+    json.get("object").and_then(|o| o.as_str()) == Some("response")
+}
+
+pub(super) fn parse_event(json: serde_json::Value) -> ParsedChunk {
+    let mut chunk = ParsedChunk::default();
+
+    // A stream-level `error` event carries `code` and `message` at the top
+    if json.get("type").and_then(|t| t.as_str()) == Some("error") {
+        chunk.error = Some(AppError::stream(&json));
+        chunk.done = true;
+        return chunk;
+    }
+
+    let Ok(event) = serde_json::from_value::<ResponsesEvent>(json) else {
+        return chunk;
+    };
+
+    match event.event_type.as_str() {
+        "response.output_text.delta" => {
+            if let Some(delta) = event.delta.filter(|delta| !delta.is_empty()) {
+                chunk.deltas.push(delta);
+            }
+        }
+        "response.reasoning_summary_text.delta" => {
+            if let Some(delta) = event.delta.filter(|delta| !delta.is_empty()) {
+                chunk.reasoning.push(delta);
+            }
+        }
+        "response.completed" | "response.incomplete" | "response.failed" => {
+            let response = event.response.unwrap_or_default();
+            chunk.error = response.error.as_ref().map(AppError::stream);
+            finish(&mut chunk, &response);
+            chunk.done = true;
+        }
+        _ => {}
+    }
+
+    chunk
+}
+
+/// Parse a complete (non-streamed) response object
+pub(super) fn parse_response(json: serde_json::Value) -> Result<ParsedChunk, String> {
+    let response: ResponseObject =
+        serde_json::from_value(json).map_err(|e| format!("Invalid response: {}", e))?;
+    if let Some(error) = &response.error {
+        return Err(format!("API error: {}", AppError::stream(error)));
+    }
+
+    let mut chunk = ParsedChunk {
+        done: true,
+        ..Default::default()
+    };
+    for item in &response.output {
+        for content in item.content.iter().chain(&item.summary) {
+            let Some(text) = content.text.clone() else {
+                continue;
+            };
+            match (item.item_type.as_str(), content.content_type.as_str()) {
+                ("message", "output_text") => chunk.deltas.push(text),
+                ("reasoning", "summary_text") => chunk.reasoning.push(text),
+                _ => {}
+            }
+        }
+    }
+    finish(&mut chunk, &response);
+
+    Ok(chunk)
+}
+
+/// Usage and finish reason of a final response object
+fn finish(chunk: &mut ParsedChunk, response: &ResponseObject) {
+    chunk.usage = response.usage.as_ref().map(|usage| TokenUsage {
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+    });
+
+    let incomplete_reason = response
+        .incomplete_details
+        .as_ref()
+        .and_then(|details| details.reason.as_deref());
+    chunk.finish_reason = Some(match (response.status.as_deref(), incomplete_reason) {
+        (Some("completed"), _) => FinishReason::Stop,
+        (_, Some("max_output_tokens")) => FinishReason::Length,
+        (_, Some("content_filter")) => FinishReason::ContentFilter,
+        _ => FinishReason::Other,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::providers::test_support::parse_stream;
+    use crate::providers::OpenAiProvider;
+
+    #[test]
+    fn reads_usage_from_completed_event() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"type":"response.created","response":{"status":"in_progress"}}"#,
+                r#"{"type":"response.output_text.delta","item_id":"msg_1","delta":"文献"}"#,
+                r#"{"type":"response.output_text.delta","item_id":"msg_1","delta":"综述"}"#,
+                r#"{"type":"response.output_text.done","item_id":"msg_1","text":"文献综述"}"#,
+                r#"{"type":"response.completed","response":{"status":"completed",
+                    "usage":{"input_tokens":20,"output_tokens":4,"total_tokens":24}}}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "文献综述");
+        assert!(parsed.done);
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            parsed.summary.usage,
+            Some(TokenUsage {
+                input_tokens: 20,
+                output_tokens: 4
+            })
+        );
+    }
+
+    #[test]
+    fn streams_reasoning_summary_separately() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"type":"response.reasoning_summary_text.delta","item_id":"rs_1","delta":"Compare "}"#,
+                r#"{"type":"response.reasoning_summary_text.delta","item_id":"rs_1","delta":"the studies"}"#,
+                r#"{"type":"response.reasoning_summary_text.done","item_id":"rs_1","text":"Compare the studies"}"#,
+                r#"{"type":"response.output_text.delta","item_id":"msg_1","delta":"Answer"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.reasoning, "Compare the studies");
+        assert_eq!(parsed.text, "Answer");
+    }
+
+    #[test]
+    fn requests_summaries_from_reasoning_models_only() {
+        assert!(is_reasoning_model("o3"));
+        assert!(is_reasoning_model("o4-mini"));
+        assert!(is_reasoning_model("gpt-5"));
+        assert!(is_reasoning_model("gpt-5-mini-2025-08-07"));
+        assert!(is_reasoning_model("openai/o3-pro"));
+        assert!(!is_reasoning_model("gpt-5-chat-latest"));
+        assert!(!is_reasoning_model("gpt-4o"));
+        assert!(!is_reasoning_model("omni-moderation-latest"));
+    }
+
+    #[test]
+    fn maps_incomplete_reasons() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"type":"response.output_text.delta","delta":"Part"}"#,
+                r#"{"type":"response.incomplete","response":{"status":"incomplete",
+                    "incomplete_details":{"reason":"max_output_tokens"}}}"#,
+            ],
+        );
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Length));
+
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"type":"response.incomplete","response":{"status":"incomplete",
+                "incomplete_details":{"reason":"content_filter"}}}"#,
+            ],
+        );
+        assert_eq!(
+            parsed.summary.finish_reason,
+            Some(FinishReason::ContentFilter)
+        );
+    }
+
+    #[test]
+    fn stops_at_error_event() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[
+                r#"{"type":"response.output_text.delta","delta":"Hello"}"#,
+                r#"{"type":"error","code":"server_error","message":"The server had an error"}"#,
+            ],
+        );
+
+        assert_eq!(parsed.text, "Hello");
+        let error = parsed.error.expect("error event");
+        assert_eq!(error.kind, ErrorKind::Server);
+        assert_eq!(error.code, "server_error");
+        assert!(error.retryable);
+    }
+
+    #[test]
+    fn reports_failed_response() {
+        let parsed = parse_stream(
+            &OpenAiProvider,
+            &[r#"{"type":"response.failed","response":{"status":"failed",
+                "error":{"code":"rate_limit_exceeded","message":"Rate limit reached"}}}"#],
+        );
+
+        let error = parsed.error.expect("failed response");
+        assert_eq!(error.kind, ErrorKind::Quota);
+        assert_eq!(error.message, "Rate limit reached");
+        assert_eq!(parsed.summary.finish_reason, Some(FinishReason::Other));
+    }
+}
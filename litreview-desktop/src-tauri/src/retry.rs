@@ -0,0 +1,69 @@
+//! Retry policy for transient provider failures.
+//!
+//! Only the phase before the first delta is retried: once text has reached the
+//! frontend, restarting the generation would duplicate it.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Per-provider retry settings (`[providers.<name>.retry]` in config.toml)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; 1 disables retrying
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    /// Upper bound for both the computed backoff and `Retry-After`
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after failed attempt number `attempt` (1-based)
+    ///
+    /// A server-provided `Retry-After` wins over exponential backoff.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let max = Duration::from_millis(self.max_backoff_ms);
+        let backoff = retry_after.unwrap_or_else(|| {
+            let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+            Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor))
+        });
+        backoff.min(max)
+    }
+}
+
+/// Rate limits, timeouts and server-side errors are worth retrying
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Connection failures and timeouts; errors in the request itself are not transient
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_body()
+}
+
+/// Parse `Retry-After` as either delay-seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(seconds as u64))
+}
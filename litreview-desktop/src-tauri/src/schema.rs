@@ -0,0 +1,391 @@
+//! config.toml schema versions and validation.
+//!
+//! The file carries a top-level `version`. Older files are upgraded step by
+//! step on load, working on the raw TOML table so that fields `AppConfig` no
+//! longer knows can still be read. `validate` covers what deserialization
+//! cannot: references between fields, URLs and value ranges.
+
+use reqwest::{Proxy, Url};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::interpolate;
+use crate::providers::{OpenAiApi, ProviderRegistry};
+use crate::{AppConfig, GenerationParams, ProviderConfig};
+
+/// Schema version written by this build
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n` table to version `n + 1`
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[migrate_v0_to_v1];
+
+/// Upgrade a parsed config.toml to `CONFIG_VERSION`, returning whether
+/// anything changed
+///
+/// Files without `version` predate versioning and count as version 0.
+pub fn migrate(table: &mut toml::Table) -> Result<bool, String> {
+    let version = match table.get("version") {
+        None => 0,
+        Some(toml::Value::Integer(version)) => {
+            u32::try_from(*version).map_err(|_| format!("Invalid config version: {}", version))?
+        }
+        Some(other) => return Err(format!("Invalid config version: {}", other)),
+    };
+
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "config.toml uses schema version {}, but this version of the app only supports up to {}. Please update the app.",
+            version, CONFIG_VERSION
+        ));
+    }
+
+    if version == CONFIG_VERSION {
+        return Ok(false);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(table);
+    }
+    table.insert("version".to_string(), i64::from(CONFIG_VERSION).into());
+
+    Ok(true)
+}
+
+/// Version 0 accepted providers written with the frontend's `provider_type`
+/// key instead of `type`
+fn migrate_v0_to_v1(table: &mut toml::Table) {
+    let Some(toml::Value::Table(providers)) = table.get_mut("providers") else {
+        return;
+    };
+
+    for (_, provider) in providers.iter_mut() {
+        let toml::Value::Table(provider) = provider else {
+            continue;
+        };
+        if provider.contains_key("type") {
+            continue;
+        }
+        if let Some(provider_type) = provider.remove("provider_type") {
+            provider.insert("type".to_string(), provider_type);
+        }
+    }
+}
+
+/// A problem with one field
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldIssue {
+    /// Dotted path of the field, e.g. "providers.openai.base_url"
+    pub field: String,
+    pub message: String,
+}
+
+/// Errors prevent saving; warnings are shown but do not
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub errors: Vec<FieldIssue>,
+    pub warnings: Vec<FieldIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// All errors on one line, for commands that can only return a string
+    pub fn error_summary(&self) -> String {
+        self.errors
+            .iter()
+            .map(|issue| format!("{}: {}", issue.field, issue.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldIssue {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(FieldIssue {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+}
+
+pub fn validate(config: &AppConfig, registry: &ProviderRegistry) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    if config.providers.is_empty() {
+        report.error("providers", "At least one provider is required");
+    } else if !config.providers.contains_key(&config.default) {
+        report.error(
+            "default",
+            format!("Provider '{}' does not exist", config.default),
+        );
+    }
+
+    for name in &config.fallback {
+        if !config.providers.contains_key(name) {
+            report.error("fallback", format!("Provider '{}' does not exist", name));
+        } else if *name == config.default {
+            report.warning(
+                "fallback",
+                format!(
+                    "'{}' is the default provider and is always tried first",
+                    name
+                ),
+            );
+        }
+    }
+
+    let mut names: Vec<_> = config.providers.keys().collect();
+    names.sort();
+    for name in names {
+        validate_provider(&mut report, name, &config.providers[name], registry);
+    }
+
+    if let Some(active) = &config.active_profile {
+        if !config.profiles.contains_key(active) {
+            report.error(
+                "active_profile",
+                format!("Profile '{}' does not exist", active),
+            );
+        }
+    }
+
+    let mut names: Vec<_> = config.profiles.keys().collect();
+    names.sort();
+    for name in names {
+        let profile = &config.profiles[name];
+        let field = |key: &str| format!("profiles.{}.{}", name, key);
+
+        if name.trim().is_empty() {
+            report.error("profiles", "Profile name must not be empty");
+        }
+        if !config.providers.contains_key(&profile.default) {
+            report.error(
+                field("default"),
+                format!("Provider '{}' does not exist", profile.default),
+            );
+        }
+        for provider in &profile.fallback {
+            if !config.providers.contains_key(provider) {
+                report.error(
+                    field("fallback"),
+                    format!("Provider '{}' does not exist", provider),
+                );
+            }
+        }
+        validate_params(&mut report, &profile.params, field);
+    }
+
+    for (model, price) in &config.pricing {
+        for (field, value) in [("input", price.input), ("output", price.output)] {
+            if value < 0.0 {
+                report.error(
+                    format!("pricing.{}.{}", model, field),
+                    "Price must not be negative",
+                );
+            }
+        }
+    }
+
+    let network = &config.network;
+    if let Some(proxy) = network.proxy.as_deref().filter(|url| !url.is_empty()) {
+        if let Err(e) = Proxy::all(proxy) {
+            report.error("network.proxy", format!("Invalid proxy URL: {}", e));
+        }
+    }
+    if let Some(path) = network.ca_bundle.as_deref().filter(|path| !path.is_empty()) {
+        if !Path::new(path).is_file() {
+            report.error("network.ca_bundle", format!("File not found: {}", path));
+        }
+    }
+    if network.insecure {
+        report.warning(
+            "network.insecure",
+            "TLS certificate verification is disabled for all providers",
+        );
+    }
+
+    report
+}
+
+fn validate_provider(
+    report: &mut ValidationReport,
+    name: &str,
+    provider: &ProviderConfig,
+    registry: &ProviderRegistry,
+) {
+    let field = |key: &str| format!("providers.{}.{}", name, key);
+
+    if name.trim().is_empty() {
+        report.error("providers", "Provider name must not be empty");
+    }
+
+    if registry.get(&provider.provider_type).is_none() {
+        report.error(
+            field("type"),
+            format!(
+                "Unsupported provider type '{}', expected one of: {}",
+                provider.provider_type,
+                registry.provider_types().join(", ")
+            ),
+        );
+    }
+
+    // Imported providers can still hold placeholders for this machine's
+    // environment; an unset variable is reported but does not block saving
+    let base_url = match interpolate::expand(&provider.base_url) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            report.warning(field("base_url"), e);
+            None
+        }
+    };
+
+    let mut is_local = false;
+    if let Some(base_url) = &base_url {
+        if base_url.trim().is_empty() {
+            report.error(field("base_url"), "Base URL is required");
+        } else {
+            match Url::parse(base_url) {
+                Ok(url) if url.scheme() != "http" && url.scheme() != "https" => {
+                    report.error(
+                        field("base_url"),
+                        "Base URL must start with http:// or https://",
+                    );
+                }
+                Ok(url) => {
+                    is_local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+                    if provider.provider_type == "ollama"
+                        && url.path().trim_end_matches('/').ends_with("/v1")
+                    {
+                        report.warning(
+                            field("base_url"),
+                            "The ollama type uses the native API; drop the OpenAI-compatible /v1 suffix",
+                        );
+                    }
+                    if provider.provider_type == "azure_openai" && url.path().contains("/openai") {
+                        report.warning(
+                            field("base_url"),
+                            "Use the resource endpoint, e.g. https://NAME.openai.azure.com; the deployment goes in the deployment field",
+                        );
+                    }
+                    if url.scheme() == "http" && !is_local && !provider.api_key.is_empty() {
+                        report.warning(
+                            field("base_url"),
+                            "The API key will be sent unencrypted over http://",
+                        );
+                    }
+                }
+                Err(e) => report.error(field("base_url"), format!("Invalid URL: {}", e)),
+            }
+        }
+    }
+
+    // Local servers such as Ollama usually run without a key
+    if provider.api_key.is_empty() && !is_local && provider.provider_type != "ollama" {
+        report.warning(field("api_key"), "No API key set");
+    }
+
+    if provider.model.trim().is_empty() {
+        report.error(field("model"), "Model is required");
+    }
+
+    if provider.context_window == Some(0) {
+        report.error(field("context_window"), "Must be greater than 0");
+    }
+
+    validate_params(report, &provider.params, field);
+
+    if provider.openai_api.is_some() && provider.provider_type != "openai" {
+        report.warning(field("openai_api"), "Only used by the openai provider type");
+    }
+
+    if provider.openai_api == Some(OpenAiApi::Responses) {
+        for (name, set) in [
+            ("temperature", provider.params.temperature.is_some()),
+            ("top_p", provider.params.top_p.is_some()),
+        ] {
+            if set {
+                report.warning(
+                    field(name),
+                    "Rejected by reasoning models on the Responses API; leave unset for them",
+                );
+            }
+        }
+    }
+
+    if provider.deployment.is_some() && provider.provider_type != "azure_openai" {
+        report.warning(
+            field("deployment"),
+            "Only used by the azure_openai provider type",
+        );
+    }
+
+    if let Some(ollama) = &provider.ollama {
+        if ollama.num_ctx == Some(0) {
+            report.error(field("ollama.num_ctx"), "Must be greater than 0");
+        }
+        if provider.provider_type != "ollama" {
+            report.warning(field("ollama"), "Only used by the ollama provider type");
+        }
+    }
+
+    if let Some(budget) = provider.thinking_budget {
+        match provider.provider_type.as_str() {
+            "claude" => {
+                if budget < 1024 {
+                    report.error(field("thinking_budget"), "Claude requires at least 1024");
+                }
+                if provider.params.max_tokens.is_some_and(|max| max <= budget) {
+                    report.error(
+                        field("max_tokens"),
+                        "Must be larger than thinking_budget, which it includes",
+                    );
+                }
+            }
+            // 0 turns thinking off on models that allow it
+            "gemini" => {}
+            _ => report.warning(
+                field("thinking_budget"),
+                "Only used by the claude and gemini provider types",
+            ),
+        }
+    }
+
+    if let Some(retry) = &provider.retry {
+        if retry.max_attempts == 0 {
+            report.error(field("retry.max_attempts"), "Must be at least 1");
+        }
+        if retry.initial_backoff_ms > retry.max_backoff_ms {
+            report.warning(
+                field("retry.initial_backoff_ms"),
+                "Larger than max_backoff_ms, which caps every delay",
+            );
+        }
+    }
+}
+
+fn validate_params(
+    report: &mut ValidationReport,
+    params: &GenerationParams,
+    field: impl Fn(&str) -> String,
+) {
+    if params
+        .temperature
+        .is_some_and(|t| !(0.0..=2.0).contains(&t))
+    {
+        report.error(field("temperature"), "Must be between 0 and 2");
+    }
+    if params.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+        report.error(field("top_p"), "Must be between 0 and 1");
+    }
+    if params.max_tokens == Some(0) {
+        report.error(field("max_tokens"), "Must be greater than 0");
+    }
+}
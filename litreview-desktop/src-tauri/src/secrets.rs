@@ -0,0 +1,175 @@
+//! API key storage outside config.toml.
+//!
+//! Keys are kept in the OS keyring (macOS Keychain, Windows Credential Manager,
+//! Secret Service on Linux) and config.toml only holds a `secret:<name>`
+//! reference. Where no keyring is reachable, e.g. headless Linux without a
+//! Secret Service daemon, keys go to `secrets.bin` in the app data directory,
+//! encrypted with AES-256-GCM under a random key in `secrets.key`. The fallback
+//! keeps keys out of config.toml and its backups; it does not protect against
+//! someone who can read the app data directory.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Keyring service name, matching the bundle identifier
+const KEYRING_SERVICE: &str = "com.lyh1999.litreview-pro";
+
+const REFERENCE_PREFIX: &str = "secret:";
+
+const NONCE_LEN: usize = 12;
+
+/// The config.toml value pointing at the secret stored under `name`
+pub fn reference(name: &str) -> String {
+    format!("{}{}", REFERENCE_PREFIX, name)
+}
+
+/// The secret name if `value` is a reference rather than a plaintext key
+pub fn parse_reference(value: &str) -> Option<&str> {
+    value.strip_prefix(REFERENCE_PREFIX)
+}
+
+pub struct SecretStore {
+    file_path: PathBuf,
+    key_path: PathBuf,
+    /// Serializes read-modify-write cycles on the fallback file
+    file_lock: Mutex<()>,
+}
+
+impl SecretStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            file_path: dir.join("secrets.bin"),
+            key_path: dir.join("secrets.key"),
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, String> {
+        match keyring_entry(name).and_then(|entry| entry.get_password()) {
+            Ok(secret) => return Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => println!("[Rust] Keyring unavailable ({}), using secrets file", e),
+        }
+
+        let _guard = self.file_lock.lock().unwrap();
+        Ok(self.read_file()?.remove(name))
+    }
+
+    pub fn set(&self, name: &str, secret: &str) -> Result<(), String> {
+        let stored = keyring_entry(name).and_then(|entry| entry.set_password(secret));
+
+        let _guard = self.file_lock.lock().unwrap();
+        let mut secrets = self.read_file()?;
+
+        match stored {
+            Ok(()) => {
+                // Drop a copy left over from a time the keyring was unavailable
+                if secrets.remove(name).is_some() {
+                    self.write_file(&secrets)?;
+                }
+            }
+            Err(e) => {
+                println!("[Rust] Keyring unavailable ({}), using secrets file", e);
+                secrets.insert(name.to_string(), secret.to_string());
+                self.write_file(&secrets)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        match keyring_entry(name).and_then(|entry| entry.delete_credential()) {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => println!("[Rust] Keyring unavailable ({}), using secrets file", e),
+        }
+
+        let _guard = self.file_lock.lock().unwrap();
+        let mut secrets = self.read_file()?;
+        if secrets.remove(name).is_some() {
+            self.write_file(&secrets)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_file(&self) -> Result<BTreeMap<String, String>, String> {
+        if !self.file_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data =
+            fs::read(&self.file_path).map_err(|e| format!("Failed to read secrets file: {}", e))?;
+        if data.len() < NONCE_LEN {
+            return Err("Secrets file is corrupted".to_string());
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Failed to decrypt secrets file".to_string())?;
+
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Failed to parse secrets file: {}", e))
+    }
+
+    fn write_file(&self, secrets: &BTreeMap<String, String>) -> Result<(), String> {
+        let plaintext = serde_json::to_vec(secrets)
+            .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| "Failed to encrypt secrets".to_string())?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_private(&self.file_path, &data)
+            .map_err(|e| format!("Failed to write secrets file: {}", e))
+    }
+
+    /// The file encryption key, generated on first use
+    fn cipher(&self) -> Result<Aes256Gcm, String> {
+        if self.key_path.exists() {
+            let key = fs::read(&self.key_path)
+                .map_err(|e| format!("Failed to read secrets key: {}", e))?;
+            if key.len() != 32 {
+                return Err("Secrets key file is corrupted".to_string());
+            }
+            return Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)));
+        }
+
+        let key = Aes256Gcm::generate_key(OsRng);
+        write_private(&self.key_path, &key)
+            .map_err(|e| format!("Failed to write secrets key: {}", e))?;
+        Ok(Aes256Gcm::new(&key))
+    }
+}
+
+fn keyring_entry(name: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(data)
+}
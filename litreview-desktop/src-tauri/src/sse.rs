@@ -0,0 +1,268 @@
+//! Server-sent events decoder shared by all providers.
+//!
+//! Implements the event stream parsing rules of the HTML spec: lines may end in
+//! `\n`, `\r\n` or `\r`, `data:` lines accumulate into one multi-line payload,
+//! and an empty line dispatches the event. Bytes are buffered until a full line
+//! is available, so multi-byte UTF-8 characters split across network chunks
+//! are decoded intact.
+
+/// A dispatched event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event:` field; `None` means the default "message" type
+    pub event: Option<String>,
+    /// All `data:` lines of the event joined with `\n`
+    pub data: String,
+    /// The last event id seen on the stream
+    pub id: Option<String>,
+    /// Reconnection time requested by the server, in milliseconds
+    pub retry: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    /// Whether the optional leading byte order mark has been checked
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a network chunk, returning the events it completes
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        if !self.started {
+            if self.buffer.len() < 3 && b"\xEF\xBB\xBF".starts_with(&self.buffer) {
+                return Vec::new();
+            }
+            if self.buffer.starts_with(b"\xEF\xBB\xBF") {
+                self.buffer.drain(..3);
+            }
+            self.started = true;
+        }
+
+        let mut events = Vec::new();
+        let mut start = 0;
+
+        while let Some(offset) = self.buffer[start..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+        {
+            let end = start + offset;
+            let next = match self.buffer[end] {
+                b'\r' => match self.buffer.get(end + 1) {
+                    Some(b'\n') => end + 2,
+                    Some(_) => end + 1,
+                    // A trailing `\r` may be the first half of `\r\n`
+                    None => break,
+                },
+                _ => end + 1,
+            };
+
+            let line = String::from_utf8_lossy(&self.buffer[start..end]).into_owned();
+            start = next;
+
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        // Drop consumed bytes once per chunk rather than once per line
+        self.buffer.drain(..start);
+
+        events
+    }
+
+    /// Flush at end of stream
+    ///
+    /// The spec discards an event that is not terminated by an empty line, but
+    /// some servers close the connection right after the last `data:` line, so
+    /// a pending event is dispatched instead.
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&self.buffer)
+                .trim_end_matches('\r')
+                .to_string();
+            self.buffer.clear();
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        if let Some(event) = self.dispatch() {
+            events.push(event);
+        }
+
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        let retry = self.retry.take();
+
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+
+        Some(SseEvent {
+            event: if event.is_empty() { None } else { Some(event) },
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+            retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(decoder.feed(chunk));
+        }
+        events.extend(decoder.finish());
+        events
+    }
+
+    fn data(events: &[SseEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.data.as_str()).collect()
+    }
+
+    #[test]
+    fn decodes_simple_events() {
+        let events = decode_chunks(&[b"data: {\"a\":1}\n\ndata: [DONE]\n\n"]);
+        assert_eq!(data(&events), vec!["{\"a\":1}", "[DONE]"]);
+        assert_eq!(events[0].event, None);
+    }
+
+    #[test]
+    fn keeps_cjk_split_across_chunks() {
+        let payload = "data: 文献综述\n\n".as_bytes();
+        // Split inside the three-byte encoding of the first character
+        let events = decode_chunks(&[&payload[..7], &payload[7..]]);
+        assert_eq!(data(&events), vec!["文献综述"]);
+    }
+
+    #[test]
+    fn byte_by_byte_matches_whole_stream() {
+        let stream = "event: content_block_delta\r\ndata: {\"text\":\"研究\"}\r\n\r\n\
+                      : keep-alive\r\n\r\ndata: second\r\n\r\n"
+            .as_bytes();
+        let chunks: Vec<&[u8]> = stream.chunks(1).collect();
+
+        assert_eq!(decode_chunks(&chunks), decode_chunks(&[stream]));
+        assert_eq!(
+            data(&decode_chunks(&chunks)),
+            vec!["{\"text\":\"研究\"}", "second"]
+        );
+    }
+
+    #[test]
+    fn handles_crlf_split_between_chunks() {
+        let events = decode_chunks(&[b"data: a\r", b"\n\r", b"\ndata: b\r\n\r\n"]);
+        assert_eq!(data(&events), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn handles_bare_cr_line_endings() {
+        let events = decode_chunks(&[b"data: a\r\rdata: b\r\r"]);
+        assert_eq!(data(&events), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let events = decode_chunks(&[b"data: {\"a\":\ndata: 1}\n\n"]);
+        assert_eq!(data(&events), vec!["{\"a\":\n1}"]);
+    }
+
+    #[test]
+    fn reads_event_id_and_retry_fields() {
+        let events = decode_chunks(&[
+            b"event: message_stop\nid: 7\nretry: 3000\ndata: {}\n\n",
+            b"data: next\n\n",
+        ]);
+
+        assert_eq!(events[0].event.as_deref(), Some("message_stop"));
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[0].retry, Some(3000));
+
+        // The event type resets after dispatch, the last event id persists
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].id.as_deref(), Some("7"));
+        assert_eq!(events[1].retry, None);
+    }
+
+    #[test]
+    fn accepts_fields_without_space_after_colon() {
+        let events = decode_chunks(&[b"event:ping\ndata:x\n\n"]);
+        assert_eq!(events[0].event.as_deref(), Some("ping"));
+        assert_eq!(events[0].data, "x");
+    }
+
+    #[test]
+    fn ignores_comments_and_events_without_data() {
+        let events = decode_chunks(&[b": comment\n\nevent: ping\n\ndata: x\n\n"]);
+        assert_eq!(data(&events), vec!["x"]);
+        assert_eq!(events[0].event, None);
+    }
+
+    #[test]
+    fn strips_leading_byte_order_mark_split_across_chunks() {
+        let events = decode_chunks(&[b"\xEF", b"\xBB\xBFdata: x\n\n"]);
+        assert_eq!(data(&events), vec!["x"]);
+    }
+
+    #[test]
+    fn dispatches_unterminated_event_on_finish() {
+        let events = decode_chunks(&[b"data: a\n\ndata: tail"]);
+        assert_eq!(data(&events), vec!["a", "tail"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Persistent usage and cost ledger.
+//!
+//! Every completed stream is appended as one JSON line to `usage.jsonl` in the
+//! app data directory. Cost is computed when the entry is recorded, from the
+//! `[pricing]` table in `config.toml`, so later price edits do not rewrite history.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::providers::{FinishReason, TokenUsage};
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// One completed or cancelled stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Local>,
+    /// Provider name (key in `AppConfig.providers`)
+    pub provider: String,
+    pub provider_type: String,
+    pub model: String,
+    /// Frontend feature that started the stream, e.g. "review" or "polish"
+    pub feature: Option<String>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub duration_ms: u64,
+    pub finish_reason: Option<FinishReason>,
+    /// `None` if the model has no price or the provider reported no usage
+    pub cost: Option<f64>,
+    /// Cancelled before it finished; usage is whatever was reported by then
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGrouping {
+    Day,
+    Provider,
+    Model,
+    Feature,
+}
+
+/// Aggregated usage for one day / provider / model / feature
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageBucket {
+    pub key: String,
+    pub requests: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    /// Requests without a known cost, not included in `cost`
+    pub unpriced_requests: u32,
+    pub duration_ms: u64,
+}
+
+pub struct UsageLedger {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl UsageLedger {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn append(&self, entry: &LedgerEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize usage entry: {}", e))?;
+
+        let _guard = self.write_lock.lock().unwrap();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create usage ledger dir: {}", e))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open usage ledger: {}", e))?;
+
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write usage ledger: {}", e))
+    }
+
+    /// All recorded entries; lines that fail to parse are skipped
+    pub fn entries(&self) -> Result<Vec<LedgerEntry>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read usage ledger: {}", e))?;
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Totals grouped by `group_by`, for entries on or after `since` (local date)
+    pub fn summarize(
+        &self,
+        group_by: UsageGrouping,
+        since: Option<NaiveDate>,
+    ) -> Result<Vec<UsageBucket>, String> {
+        let mut buckets: BTreeMap<String, UsageBucket> = BTreeMap::new();
+
+        for entry in self.entries()? {
+            if since.is_some_and(|since| entry.timestamp.date_naive() < since) {
+                continue;
+            }
+
+            let key = match group_by {
+                UsageGrouping::Day => entry.timestamp.format("%Y-%m-%d").to_string(),
+                UsageGrouping::Provider => entry.provider.clone(),
+                UsageGrouping::Model => entry.model.clone(),
+                UsageGrouping::Feature => entry.feature.clone().unwrap_or_default(),
+            };
+
+            let bucket = buckets.entry(key.clone()).or_insert_with(|| UsageBucket {
+                key,
+                ..Default::default()
+            });
+            bucket.requests += 1;
+            bucket.input_tokens += entry.input_tokens as u64;
+            bucket.output_tokens += entry.output_tokens as u64;
+            bucket.duration_ms += entry.duration_ms;
+            match entry.cost {
+                Some(cost) => bucket.cost += cost,
+                None => bucket.unpriced_requests += 1,
+            }
+        }
+
+        Ok(buckets.into_values().collect())
+    }
+}
@@ -25,16 +25,27 @@ function App() {
   
   const { mode: themeMode, setTheme } = useTheme();
   const { toasts, showSuccess, showToast, closeToast } = useToast();
-  const { content, loading, error, startStream, reset } = useLlmStream();
+  const { content, reasoning, loading, error, startStream, reset } = useLlmStream();
   const { 
     config, 
     appConfig,
     loading: configLoading, 
     saving, 
     configPath,
+    configError,
+    configNotices,
     saveAppConfig,
     setDefaultProvider,
     deleteProvider,
+    validateConfig,
+    listConfigBackups,
+    restoreConfigBackup,
+    exportConfig,
+    importConfig,
+    createProfile,
+    switchProfile,
+    duplicateProfile,
+    listModels,
     testConnection,
   } = useConfig();
 
@@ -43,14 +54,25 @@ function App() {
 
   const handleGenerate = async (prompt: string) => {
     if (!config) return;
-    await startStream(prompt, config);
+    await startStream(prompt, config, { feature: "review" });
   };
 
   const handlePolish = async (prompt: string, systemPrompt?: string) => {
     if (!config) return;
-    await startStream(prompt, config, systemPrompt);
+    await startStream(prompt, config, { systemPrompt, feature: "polish" });
   };
 
+  // config.toml was edited outside the app into an unusable state
+  useEffect(() => {
+    if (configError) {
+      showToast(`配置文件错误：${configError.message}`, 'error', 8000);
+    }
+  }, [configError]);
+
+  useEffect(() => {
+    configNotices.forEach((notice) => showToast(`配置迁移：${notice}`, 'warning', 8000));
+  }, [configNotices]);
+
   // Global keyboard shortcuts
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -86,6 +108,7 @@ function App() {
               config={config}
               providerName={providerName}
               content={content}
+              reasoning={reasoning}
               loading={loading}
               error={error}
               onGenerate={handleGenerate}
@@ -106,6 +129,7 @@ function App() {
               loading={loading}
               error={error}
               content={content}
+              reasoning={reasoning}
               onPolish={handlePolish}
               onReset={reset}
               showToast={(message, type) => {
@@ -125,6 +149,15 @@ function App() {
               onSaveAppConfig={saveAppConfig}
               onSetDefault={setDefaultProvider}
               onDeleteProvider={deleteProvider}
+              onValidateConfig={validateConfig}
+              onListBackups={listConfigBackups}
+              onRestoreBackup={restoreConfigBackup}
+              onExportConfig={exportConfig}
+              onImportConfig={importConfig}
+              onCreateProfile={createProfile}
+              onSwitchProfile={switchProfile}
+              onDuplicateProfile={duplicateProfile}
+              onListModels={listModels}
               onTestConnection={testConnection}
               themeMode={themeMode}
               onThemeChange={setTheme}
@@ -1,5 +1,16 @@
-import { useState, useEffect } from "react";
-import { AppConfig, ProviderConfig } from "../hooks/useLlmStream";
+import { useState, useEffect, useRef } from "react";
+import {
+  AppConfig,
+  ConfigBackup,
+  ConflictStrategy,
+  DiagnosticReport,
+  ImportResult,
+  ModelInfo,
+  OpenAiApi,
+  ProviderConfig,
+  ValidationReport,
+  describeError,
+} from "../hooks/useLlmStream";
 import { PROVIDER_TYPE_TEMPLATES } from "../hooks/useConfig";
 import type { ThemeMode } from "../hooks/useTheme";
 import { GlassSelect } from "./GlassSelect";
@@ -11,7 +22,16 @@ interface ApiConfigPageProps {
   onSaveAppConfig: (config: AppConfig) => Promise<void>;
   onSetDefault: (providerName: string) => Promise<void>;
   onDeleteProvider: (name: string) => Promise<void>;
-  onTestConnection: (provider: ProviderConfig) => Promise<void>;
+  onValidateConfig: (config: AppConfig) => Promise<ValidationReport>;
+  onListBackups: () => Promise<ConfigBackup[]>;
+  onRestoreBackup: (name: string) => Promise<void>;
+  onExportConfig: (includeKeys: boolean) => Promise<string>;
+  onImportConfig: (content: string, onConflict: ConflictStrategy) => Promise<ImportResult>;
+  onCreateProfile: (name: string) => Promise<void>;
+  onSwitchProfile: (name: string) => Promise<void>;
+  onDuplicateProfile: (source: string, name: string) => Promise<void>;
+  onListModels: (providerName: string, refresh?: boolean) => Promise<ModelInfo[]>;
+  onTestConnection: (name: string, provider: ProviderConfig) => Promise<DiagnosticReport>;
   themeMode: ThemeMode;
   onThemeChange: (mode: ThemeMode) => void;
 }
@@ -22,10 +42,25 @@ const THEME_OPTIONS: { value: ThemeMode; label: string }[] = [
   { value: "system", label: "Auto" },
 ];
 
+const formatMs = (ms: number | null) => (ms === null ? "—" : `${ms} ms`);
+
+const OPENAI_API_OPTIONS: { value: OpenAiApi; label: string }[] = [
+  { value: "chat_completions", label: "Chat Completions (/chat/completions)" },
+  { value: "responses", label: "Responses (/responses)" },
+];
+
+const CONFLICT_OPTIONS: { value: ConflictStrategy; label: string }[] = [
+  { value: "skip", label: "同名跳过" },
+  { value: "replace", label: "同名覆盖" },
+  { value: "rename", label: "同名重命名" },
+];
+
 const PROVIDER_TYPES = [
   { value: "openai", label: "OpenAI Compatible" },
   { value: "claude", label: "Claude Compatible" },
   { value: "gemini", label: "Gemini Compatible" },
+  { value: "ollama", label: "Ollama" },
+  { value: "azure_openai", label: "Azure OpenAI" },
 ];
 
 export function ApiConfigPage({
@@ -35,6 +70,15 @@ export function ApiConfigPage({
   onSaveAppConfig,
   onSetDefault,
   onDeleteProvider,
+  onValidateConfig,
+  onListBackups,
+  onRestoreBackup,
+  onExportConfig,
+  onImportConfig,
+  onCreateProfile,
+  onSwitchProfile,
+  onDuplicateProfile,
+  onListModels,
   onTestConnection,
   themeMode,
   onThemeChange,
@@ -44,8 +88,16 @@ export function ApiConfigPage({
   const [editingName, setEditingName] = useState<string>("");
   const [isNewProvider, setIsNewProvider] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [validation, setValidation] = useState<ValidationReport | null>(null);
   const [testResult, setTestResult] = useState<string | null>(null);
   const [testing, setTesting] = useState(false);
+  const [diagnostic, setDiagnostic] = useState<DiagnosticReport | null>(null);
+  const [backups, setBackups] = useState<ConfigBackup[] | null>(null);
+  const [models, setModels] = useState<{ provider: string; list: ModelInfo[] } | null>(null);
+  const [loadingModels, setLoadingModels] = useState(false);
+  const [exportKeys, setExportKeys] = useState(false);
+  const [conflictStrategy, setConflictStrategy] = useState<ConflictStrategy>("skip");
+  const importInputRef = useRef<HTMLInputElement>(null);
 
   // Initialize selected provider
   useEffect(() => {
@@ -56,6 +108,7 @@ export function ApiConfigPage({
       setEditingName(defaultName);
       setIsNewProvider(false);
       setError(null);
+      setValidation(null);
       setTestResult(null);
     }
   }, [appConfig]);
@@ -69,24 +122,23 @@ export function ApiConfigPage({
   }
 
   const providerNames = Object.keys(appConfig.providers);
+  const fieldPrefix = `providers.${editingName}.`;
+  const modelList = !isNewProvider && models?.provider === selectedProvider ? models.list : null;
+  const unknownModel =
+    modelList && editingProvider?.model && !modelList.some((m) => m.id === editingProvider.model);
 
   const handleSelectProvider = (name: string) => {
     const provider = appConfig.providers[name];
     
     setSelectedProvider(name);
     // Use actual saved values, not templates
-    setEditingProvider({
-      provider_type: provider.provider_type,
-      base_url: provider.base_url,
-      api_key: provider.api_key,
-      model: provider.model,
-      context_window: provider.context_window,
-      api_version: provider.api_version,
-    });
+    setEditingProvider({ ...provider });
     setEditingName(name);
     setIsNewProvider(false);
     setError(null);
+    setValidation(null);
     setTestResult(null);
+    setDiagnostic(null);
   };
 
   const handleAddNew = () => {
@@ -103,7 +155,9 @@ export function ApiConfigPage({
     setEditingName("");
     setIsNewProvider(true);
     setError(null);
+    setValidation(null);
     setTestResult(null);
+    setDiagnostic(null);
   };
 
   const handleProviderTypeChange = (providerType: string) => {
@@ -117,6 +171,13 @@ export function ApiConfigPage({
       model: template.model || editingProvider.model,
       context_window: template.context_window,
       api_version: template.api_version,
+      openai_api: providerType === "openai" ? editingProvider.openai_api : undefined,
+      deployment: providerType === "azure_openai" ? editingProvider.deployment : undefined,
+      ollama: providerType === "ollama" ? editingProvider.ollama : undefined,
+      thinking_budget:
+        providerType === "claude" || providerType === "gemini"
+          ? editingProvider.thinking_budget
+          : undefined,
     });
   };
 
@@ -143,9 +204,23 @@ export function ApiConfigPage({
       }
       
       const newConfig: AppConfig = {
+        ...appConfig,
         default: newDefault,
         providers: newProviders,
       };
+
+      const report = await onValidateConfig(newConfig);
+      setValidation(report);
+      if (report.errors.length > 0) {
+        // Errors on fields of this form are shown next to them
+        const otherErrors = report.errors.filter((issue) => !issue.field.startsWith(fieldPrefix));
+        setError(
+          otherErrors.length > 0
+            ? otherErrors.map((issue) => `${issue.field}: ${issue.message}`).join("; ")
+            : "请修正标记的字段",
+        );
+        return;
+      }
       
       await onSaveAppConfig(newConfig);
       
@@ -153,10 +228,25 @@ export function ApiConfigPage({
       setIsNewProvider(false);
       setTestResult("✓ 保存成功！");
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
+  const renderFieldIssues = (field: string) => {
+    if (!validation) return null;
+    const path = fieldPrefix + field;
+    return (
+      <>
+        {validation.errors.filter((issue) => issue.field === path).map((issue) => (
+          <small key={`error-${issue.message}`} className="hint field-error">{issue.message}</small>
+        ))}
+        {validation.warnings.filter((issue) => issue.field === path).map((issue) => (
+          <small key={`warning-${issue.message}`} className="hint field-warning">{issue.message}</small>
+        ))}
+      </>
+    );
+  };
+
   const handleDelete = async () => {
     if (!selectedProvider) return;
     
@@ -174,7 +264,7 @@ export function ApiConfigPage({
         handleSelectProvider(remaining[0]);
       }
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
@@ -185,7 +275,7 @@ export function ApiConfigPage({
       await onSetDefault(selectedProvider);
       setTestResult("✓ 已设为默认！");
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
@@ -195,21 +285,147 @@ export function ApiConfigPage({
     setTesting(true);
     setTestResult(null);
     setError(null);
+    setDiagnostic(null);
 
     try {
-      await onTestConnection(editingProvider);
-      setTestResult("✓ 连接成功！");
+      const report = await onTestConnection(editingName, editingProvider);
+      setDiagnostic(report);
+      if (report.ok) {
+        setTestResult("✓ 连接成功！");
+      } else if (report.error) {
+        setError(describeError(report.error));
+      }
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     } finally {
       setTesting(false);
     }
   };
 
+  const handleLoadModels = async () => {
+    if (!selectedProvider) return;
+
+    setLoadingModels(true);
+    setError(null);
+    try {
+      // A second click fetches a fresh list
+      const refresh = models?.provider === selectedProvider;
+      const list = await onListModels(selectedProvider, refresh);
+      setModels({ provider: selectedProvider, list });
+    } catch (e) {
+      setError(describeError(e));
+    } finally {
+      setLoadingModels(false);
+    }
+  };
+
+  const handleShowBackups = async () => {
+    try {
+      setBackups(await onListBackups());
+    } catch (e) {
+      setError(describeError(e));
+    }
+  };
+
+  const handleRestoreBackup = async (backup: ConfigBackup) => {
+    const created = new Date(backup.created).toLocaleString();
+    if (!confirm(`恢复 ${created} 的配置备份？当前配置会先自动备份。`)) return;
+
+    try {
+      await onRestoreBackup(backup.name);
+      setBackups(null);
+      setTestResult("✓ 已恢复备份！");
+    } catch (e) {
+      setError(describeError(e));
+    }
+  };
+
+  const handleExport = async () => {
+    setError(null);
+    try {
+      const path = await onExportConfig(exportKeys);
+      setTestResult(`✓ 已导出到 ${path}`);
+    } catch (e) {
+      setError(describeError(e));
+    }
+  };
+
+  const handleImportFile = async (e: React.ChangeEvent<HTMLInputElement>) => {
+    const file = e.target.files?.[0];
+    e.target.value = "";
+    if (!file) return;
+
+    setError(null);
+    try {
+      const result = await onImportConfig(await file.text(), conflictStrategy);
+      const parts = [
+        result.added.length > 0 && `新增 ${result.added.join(", ")}`,
+        result.replaced.length > 0 && `覆盖 ${result.replaced.join(", ")}`,
+        result.renamed.length > 0 &&
+          `重命名 ${result.renamed.map((r) => `${r.from} → ${r.to}`).join(", ")}`,
+        result.skipped.length > 0 && `跳过 ${result.skipped.join(", ")}`,
+      ].filter(Boolean);
+      setTestResult(`✓ 导入完成：${parts.join("；")}`);
+    } catch (e) {
+      setError(describeError(e));
+    }
+  };
+
+  const runProfileAction = async (action: () => Promise<void>, message: string) => {
+    setError(null);
+    try {
+      await action();
+      setTestResult(message);
+    } catch (e) {
+      setError(describeError(e));
+    }
+  };
+
+  const handleCreateProfile = async () => {
+    const name = prompt("新配置方案名称（例如：work, personal, offline）")?.trim();
+    if (!name) return;
+    await runProfileAction(() => onCreateProfile(name), `✓ 已创建配置方案 ${name}`);
+  };
+
+  const handleDuplicateProfile = async () => {
+    const source = appConfig.active_profile;
+    if (!source) return;
+    const name = prompt(`复制配置方案 ${source} 为：`, `${source}-copy`)?.trim();
+    if (!name) return;
+    await runProfileAction(() => onDuplicateProfile(source, name), `✓ 已复制为 ${name}`);
+  };
+
+  const profileOptions = Object.keys(appConfig.profiles ?? {})
+    .sort()
+    .map((name) => ({ value: name, label: name }));
+
   return (
     <div className="page-container">
       <h2 className="page-title">API 配置</h2>
 
+      <div className="profile-bar">
+        <span>配置方案</span>
+        {profileOptions.length > 0 && (
+          <GlassSelect
+            id="active-profile"
+            value={appConfig.active_profile ?? ""}
+            options={profileOptions}
+            placeholder="选择配置方案"
+            onChange={(name) =>
+              runProfileAction(() => onSwitchProfile(name), `✓ 已切换到 ${name}`)
+            }
+          />
+        )}
+        <button type="button" className="link-btn" onClick={handleCreateProfile} disabled={saving}>
+          <small>新建</small>
+        </button>
+        {appConfig.active_profile && (
+          <button type="button" className="link-btn" onClick={handleDuplicateProfile} disabled={saving}>
+            <small>复制</small>
+          </button>
+        )}
+      </div>
+
       <div className="config-layout">
         {/* Left: Provider List */}
         <div className="provider-list">
@@ -270,8 +486,9 @@ export function ApiConfigPage({
                   onChange={handleProviderTypeChange}
                 />
                 <small className="hint">
-                  OpenAI Compatible 支持 DeepSeek, Moonshot, Ollama 等
+                  OpenAI Compatible 支持 DeepSeek, Moonshot 等；本地 Ollama 请选择 Ollama
                 </small>
+                {renderFieldIssues("type")}
               </div>
 
               <div className="form-group">
@@ -283,6 +500,7 @@ export function ApiConfigPage({
                   onChange={(e) => setEditingProvider({ ...editingProvider, base_url: e.target.value })}
                   placeholder={PROVIDER_TYPE_TEMPLATES[editingProvider.provider_type]?.base_url || ""}
                 />
+                {renderFieldIssues("base_url")}
               </div>
 
               <div className="form-group">
@@ -294,6 +512,7 @@ export function ApiConfigPage({
                   onChange={(e) => setEditingProvider({ ...editingProvider, api_key: e.target.value })}
                   placeholder="sk-..."
                 />
+                {renderFieldIssues("api_key")}
               </div>
 
               <div className="form-group">
@@ -304,10 +523,73 @@ export function ApiConfigPage({
                   value={editingProvider.model}
                   onChange={(e) => setEditingProvider({ ...editingProvider, model: e.target.value })}
                   placeholder={PROVIDER_TYPE_TEMPLATES[editingProvider.provider_type]?.model || ""}
+                  list="model-options"
                 />
+                {modelList && (
+                  <datalist id="model-options">
+                    {modelList.map((m) => (
+                      <option key={m.id} value={m.id}>
+                        {[m.display_name, m.context_window && `${m.context_window} tokens`]
+                          .filter(Boolean)
+                          .join(" · ")}
+                      </option>
+                    ))}
+                  </datalist>
+                )}
+                {!isNewProvider && (
+                  <button
+                    type="button"
+                    className="link-btn"
+                    onClick={handleLoadModels}
+                    disabled={loadingModels}
+                  >
+                    <small>{loadingModels ? "获取中..." : modelList ? "刷新模型列表" : "获取模型列表"}</small>
+                  </button>
+                )}
+                {unknownModel && (
+                  <small className="hint field-warning">该 provider 的模型列表中没有此模型</small>
+                )}
+                {renderFieldIssues("model")}
               </div>
 
-              {editingProvider.provider_type === "claude" && (
+              {editingProvider.provider_type === "openai" && (
+                <div className="form-group">
+                  <label htmlFor="openai-api">接口</label>
+                  <GlassSelect
+                    id="openai-api"
+                    value={editingProvider.openai_api ?? "chat_completions"}
+                    options={OPENAI_API_OPTIONS}
+                    onChange={(value) =>
+                      setEditingProvider({
+                        ...editingProvider,
+                        openai_api: value === "responses" ? "responses" : undefined,
+                      })
+                    }
+                  />
+                  <small className="hint">Responses 仅 OpenAI 官方支持，适合 o 系列等推理模型</small>
+                  {renderFieldIssues("openai_api")}
+                </div>
+              )}
+
+              {editingProvider.provider_type === "azure_openai" && (
+                <div className="form-group">
+                  <label htmlFor="deployment">部署名称 (Deployment)</label>
+                  <input
+                    id="deployment"
+                    type="text"
+                    value={editingProvider.deployment || ""}
+                    onChange={(e) =>
+                      setEditingProvider({ ...editingProvider, deployment: e.target.value || undefined })
+                    }
+                    placeholder={editingProvider.model || "与模型名相同"}
+                  />
+                  <small className="hint">Azure 门户中的部署名；留空时使用模型名</small>
+                  {renderFieldIssues("deployment")}
+                </div>
+              )}
+
+              {(editingProvider.provider_type === "claude" ||
+                editingProvider.provider_type === "azure_openai") && (
                 <div className="form-group">
                   <label htmlFor="api_version">API 版本</label>
                   <input
@@ -315,13 +597,121 @@ export function ApiConfigPage({
                     type="text"
                     value={editingProvider.api_version || ""}
                     onChange={(e) => setEditingProvider({ ...editingProvider, api_version: e.target.value })}
-                    placeholder="2023-06-01"
+                    placeholder={PROVIDER_TYPE_TEMPLATES[editingProvider.provider_type]?.api_version}
+                  />
+                </div>
+              )}
+
+              {(editingProvider.provider_type === "claude" ||
+                editingProvider.provider_type === "gemini") && (
+                <div className="form-group">
+                  <label htmlFor="thinking_budget">思考预算 (tokens)</label>
+                  <input
+                    id="thinking_budget"
+                    type="number"
+                    min={0}
+                    value={editingProvider.thinking_budget ?? ""}
+                    onChange={(e) =>
+                      setEditingProvider({
+                        ...editingProvider,
+                        thinking_budget: e.target.value ? Number(e.target.value) : undefined,
+                      })
+                    }
+                    placeholder="不启用"
                   />
+                  <small className="hint">启用扩展思考，思考过程单独显示；Claude 至少 1024</small>
+                  {renderFieldIssues("thinking_budget")}
                 </div>
               )}
 
+              {editingProvider.provider_type === "ollama" && (
+                <>
+                  <div className="form-group">
+                    <label htmlFor="ollama_num_ctx">上下文长度 (num_ctx)</label>
+                    <input
+                      id="ollama_num_ctx"
+                      type="number"
+                      min={1}
+                      value={editingProvider.ollama?.num_ctx ?? ""}
+                      onChange={(e) =>
+                        setEditingProvider({
+                          ...editingProvider,
+                          ollama: {
+                            ...editingProvider.ollama,
+                            num_ctx: e.target.value ? Number(e.target.value) : undefined,
+                          },
+                        })
+                      }
+                      placeholder="模型默认"
+                    />
+                    <small className="hint">Ollama 默认上下文较短，长文献综述建议设为 32768 或更高</small>
+                    {renderFieldIssues("ollama.num_ctx")}
+                  </div>
+
+                  <div className="form-group">
+                    <label htmlFor="ollama_keep_alive">保持加载 (keep_alive)</label>
+                    <input
+                      id="ollama_keep_alive"
+                      type="text"
+                      value={editingProvider.ollama?.keep_alive ?? ""}
+                      onChange={(e) =>
+                        setEditingProvider({
+                          ...editingProvider,
+                          ollama: { ...editingProvider.ollama, keep_alive: e.target.value || undefined },
+                        })
+                      }
+                      placeholder="5m"
+                    />
+                  </div>
+
+                  <div className="form-group">
+                    <label>
+                      <input
+                        type="checkbox"
+                        checked={editingProvider.ollama?.raw ?? false}
+                        onChange={(e) =>
+                          setEditingProvider({
+                            ...editingProvider,
+                            ollama: { ...editingProvider.ollama, raw: e.target.checked || undefined },
+                          })
+                        }
+                      />
+                      Raw 模式（不套用模型的提示词模板）
+                    </label>
+                  </div>
+                </>
+              )}
+
               {error && <div className="error-message">{error}</div>}
               {testResult && <div className="success-message">{testResult}</div>}
+              {diagnostic && (
+                <ul className="diagnostic-report">
+                  {diagnostic.proxy && <li>代理：{diagnostic.proxy}</li>}
+                  <li>
+                    DNS {formatMs(diagnostic.dns_ms)} · 连接 {formatMs(diagnostic.connect_ms)} · TLS{" "}
+                    {formatMs(diagnostic.tls_ms)}
+                  </li>
+                  {diagnostic.status !== null && (
+                    <li>
+                      HTTP {diagnostic.status} · 响应 {formatMs(diagnostic.response_ms)}
+                    </li>
+                  )}
+                  {diagnostic.streaming !== null && (
+                    <li>
+                      流式输出：{diagnostic.streaming ? "正常" : "不可用"} · 首字{" "}
+                      {formatMs(diagnostic.first_token_ms)} · 总计 {formatMs(diagnostic.stream_ms)}
+                    </li>
+                  )}
+                  {diagnostic.warnings.map((warning) => (
+                    <li key={warning} className="field-warning">{warning}</li>
+                  ))}
+                  {diagnostic.error?.raw && (
+                    <li>
+                      <code>{diagnostic.error.raw}</code>
+                    </li>
+                  )}
+                </ul>
+              )}
 
               <div className="button-group">
                 <button type="button" onClick={handleTest} disabled={testing || saving}>
@@ -369,6 +759,63 @@ export function ApiConfigPage({
 
       <div className="config-path">
         <small>配置文件：{configPath}</small>
+        {" "}
+        <button type="button" className="link-btn" onClick={handleShowBackups} disabled={saving}>
+          <small>历史备份</small>
+        </button>
+        <div className="share-bar">
+          <button type="button" className="link-btn" onClick={handleExport} disabled={saving}>
+            <small>导出 Providers</small>
+          </button>
+          <label>
+            <input
+              type="checkbox"
+              checked={exportKeys}
+              onChange={(e) => setExportKeys(e.target.checked)}
+            />
+            <small>包含 API Key</small>
+          </label>
+          <button
+            type="button"
+            className="link-btn"
+            onClick={() => importInputRef.current?.click()}
+            disabled={saving}
+          >
+            <small>导入 Providers</small>
+          </button>
+          <GlassSelect
+            id="import-conflict"
+            value={conflictStrategy}
+            options={CONFLICT_OPTIONS}
+            onChange={(value) => setConflictStrategy(value as ConflictStrategy)}
+          />
+          <input
+            ref={importInputRef}
+            type="file"
+            accept=".toml"
+            hidden
+            onChange={handleImportFile}
+          />
+        </div>
+        {backups && (
+          <ul className="backup-list">
+            {backups.length === 0 && <li><small>暂无备份</small></li>}
+            {backups.map((backup) => (
+              <li key={backup.name}>
+                <small>{new Date(backup.created).toLocaleString()}</small>
+                {" "}
+                <button
+                  type="button"
+                  className="link-btn"
+                  onClick={() => handleRestoreBackup(backup)}
+                  disabled={saving}
+                >
+                  <small>恢复</small>
+                </button>
+              </li>
+            ))}
+          </ul>
+        )}
       </div>
     </div>
   );
@@ -4,8 +4,9 @@
  */
 
 import React, { useState, useEffect, useMemo } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import type { TabType } from '../types/tabs';
-import type { ProviderConfig } from '../hooks/useLlmStream';
+import type { ProviderConfig, UsageBucket } from '../hooks/useLlmStream';
 import { useBreakpoint } from '../hooks/useDesignTokens';
 import { StatCard } from './common/StatCard';
 import { ActionCard } from './common/ActionCard';
@@ -93,15 +94,32 @@ export function HomePage({ config, providerName, onNavigate, className = '' }: H
     providerUsage: {}
   });
 
-  // Load and calculate stats from real history data
+  // Counts come from the usage ledger; activity charts from local history
   useEffect(() => {
-    const loadStats = () => {
+    const loadStats = async () => {
       // Load history from localStorage
       const savedHistory = localStorage.getItem(STORAGE_KEYS.HISTORY);
       const historyData = savedHistory ? JSON.parse(savedHistory) : [];
 
-      const generationCount = parseInt(localStorage.getItem(STORAGE_KEYS.GENERATION_COUNT) || '0', 10);
-      const polishCount = parseInt(localStorage.getItem('litreview_polish_count') || '0', 10);
+      let features: UsageBucket[] = [];
+      let providers: UsageBucket[] = [];
+      try {
+        [features, providers] = await Promise.all([
+          invoke<UsageBucket[]>('get_usage_stats', { groupBy: 'feature' }),
+          invoke<UsageBucket[]>('get_usage_stats', { groupBy: 'provider' }),
+        ]);
+      } catch (err) {
+        console.error('Failed to load usage stats:', err);
+      }
+
+      const requestsFor = (feature: string) =>
+        features.find(bucket => bucket.key === feature)?.requests ?? 0;
+      const generationCount = requestsFor('review');
+      const polishCount = requestsFor('polish');
+
+      const totalRequests = features.reduce((sum, bucket) => sum + bucket.requests, 0);
+      const totalDuration = features.reduce((sum, bucket) => sum + bucket.duration_ms, 0);
+      const averageResponseTime = totalRequests > 0 ? Math.round(totalDuration / totalRequests) : 0;
 
       // Calculate total characters from history
       const totalCharacters = historyData.reduce((sum: number, item: any) =>
@@ -133,18 +151,16 @@ export function HomePage({ config, providerName, onNavigate, className = '' }: H
         weekData[0]  // Sunday
       ];
 
-      // Calculate provider usage from actual history
       const providerUsage: Record<string, number> = {};
-      historyData.forEach((item: any) => {
-        const provider = item.provider || 'Unknown';
-        providerUsage[provider] = (providerUsage[provider] || 0) + 1;
+      providers.forEach(bucket => {
+        providerUsage[bucket.key || 'Unknown'] = bucket.requests;
       });
 
       setStats({
         generationCount,
         polishCount,
         totalCharacters,
-        averageResponseTime: 0, // Not tracking response time currently
+        averageResponseTime,
         weeklyActivity,
         providerUsage
       });
@@ -8,6 +8,7 @@ import {
   CheckIcon,
 } from "./icons";
 import styles from "./LanguagePolish.module.css";
+import { ReasoningPanel } from "./common/ReasoningPanel";
 
 // ============================================================================
 // Type Definitions
@@ -387,6 +388,7 @@ interface LanguagePolishProps {
   loading: boolean;
   error: string | null;
   content: string;
+  reasoning: string;
   onPolish: (prompt: string, systemPrompt?: string) => Promise<void>;
   onReset: () => void;
   showToast?: (message: string, type?: 'success' | 'error' | 'warning' | 'info') => void;
@@ -397,6 +399,7 @@ export function LanguagePolish({
   loading,
   error,
   content,
+  reasoning,
   onPolish,
   onReset,
   showToast,
@@ -567,6 +570,7 @@ export function LanguagePolish({
             </div>
           </div>
           <div className={styles.resultContainer}>
+            <ReasoningPanel reasoning={reasoning} answering={!!content} />
             {polishedText ? (
               <div className={styles.resultContent}>{polishedText}</div>
             ) : (
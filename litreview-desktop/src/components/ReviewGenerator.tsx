@@ -9,6 +9,7 @@ import { STORAGE_KEYS, HISTORY_MAX_ITEMS, AUTO_SAVE_DELAY, ANIMATION_DELAY_BASE
 import { TemplateCard, type Template } from './review/TemplateCard';
 import { HistoryItem, type ReviewHistory } from './review/HistoryItem';
 import { WordCounter } from './common/WordCounter';
+import { ReasoningPanel } from './common/ReasoningPanel';
 import {
   BookIcon,
   ZapIcon,
@@ -205,6 +206,7 @@ interface ReviewGeneratorProps {
   config: ProviderConfig | null;
   providerName: string;
   content: string;
+  reasoning: string;
   loading: boolean;
   error: string | null;
   onGenerate: (prompt: string) => Promise<void>;
@@ -216,6 +218,7 @@ export function ReviewGenerator({
   config,
   providerName,
   content,
+  reasoning,
   loading,
   error,
   onGenerate,
@@ -345,10 +348,6 @@ export function ReviewGenerator({
     e.preventDefault();
     if (!prompt.trim() || !config) return;
 
-    // Update generation count
-    const currentCount = parseInt(localStorage.getItem(STORAGE_KEYS.GENERATION_COUNT) || '0', 10);
-    localStorage.setItem(STORAGE_KEYS.GENERATION_COUNT, String(currentCount + 1));
-
     // Save to history
     const historyItem: ReviewHistory = {
       id: Date.now().toString(),
@@ -413,10 +412,6 @@ export function ReviewGenerator({
     setPrompt(item.prompt);
     setShowHistory(false);
 
-    // Update generation count for restored item
-    const currentCount = parseInt(localStorage.getItem(STORAGE_KEYS.GENERATION_COUNT) || '0', 10);
-    localStorage.setItem(STORAGE_KEYS.GENERATION_COUNT, String(currentCount + 1));
-
     if (item.result) {
       onGenerate(item.prompt);
     }
@@ -760,6 +755,8 @@ export function ReviewGenerator({
             </div>
           </div>
 
+          <ReasoningPanel reasoning={reasoning} answering={!!content} />
+
           <div className={styles.outputContent}>
             {content ? (
               isPreviewMode ? (
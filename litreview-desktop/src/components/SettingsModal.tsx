@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { AppConfig, ProviderConfig } from "../hooks/useLlmStream";
+import { AppConfig, ProviderConfig, describeError } from "../hooks/useLlmStream";
 import { PROVIDER_TYPE_TEMPLATES } from "../hooks/useConfig";
 import type { ThemeMode } from "../hooks/useTheme";
 import { GlassSelect } from "./GlassSelect";
@@ -152,7 +152,7 @@ export function SettingsModal({
       setIsNewProvider(false);
       setTestResult("✓ Saved successfully!");
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
@@ -174,7 +174,7 @@ export function SettingsModal({
         handleSelectProvider(remaining[0]);
       }
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
@@ -185,7 +185,7 @@ export function SettingsModal({
       await onSetDefault(selectedProvider);
       setTestResult("✓ Set as default!");
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     }
   };
 
@@ -213,7 +213,7 @@ export function SettingsModal({
       
       setTestResult("✓ Connection successful!");
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
     } finally {
       setTesting(false);
     }
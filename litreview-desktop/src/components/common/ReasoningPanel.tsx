@@ -0,0 +1,20 @@
+import React from 'react';
+import styles from './ReasoningPanel.module.css';
+
+// Collapsible view of the model's reasoning; open while only reasoning has arrived
+export const ReasoningPanel: React.FC<{ reasoning: string; answering: boolean }> = ({
+  reasoning,
+  answering,
+}) => {
+  if (!reasoning) return null;
+
+  return (
+    <details className={styles.reasoningPanel} open={!answering}>
+      <summary className={styles.reasoningSummary}>
+        思考过程
+        <span className={styles.reasoningCount}>{reasoning.length.toLocaleString()} 字符</span>
+      </summary>
+      <div className={styles.reasoningContent}>{reasoning}</div>
+    </details>
+  );
+};
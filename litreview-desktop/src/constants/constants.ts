@@ -8,9 +8,6 @@
 // ============================================================================
 
 export const STORAGE_KEYS = {
-  GENERATION_COUNT: 'litreview_generation_count',
-  POLISH_COUNT: 'litreview_polish_count',
-  TOTAL_CHARACTERS: 'litreview_total_characters',
   DRAFT_PROMPT: 'litreview_draft_prompt',
   HISTORY: 'litreview_history',
 } as const;
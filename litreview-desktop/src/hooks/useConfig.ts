@@ -1,39 +1,170 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { LlmConfig, AppConfig, ProviderConfig } from "./useLlmStream";
+import { listen } from "@tauri-apps/api/event";
+import {
+  LlmConfig,
+  AppConfig,
+  ProviderConfig,
+  GenerationParams,
+  ModelPrice,
+  NetworkConfig,
+  RetryPolicy,
+  ValidationReport,
+  ConfigBackup,
+  Profile,
+  ProfileSummary,
+  ConflictStrategy,
+  ImportResult,
+  ModelInfo,
+  DiagnosticReport,
+  OllamaOptions,
+  OpenAiApi,
+} from "./useLlmStream";
 
-interface RustProviderConfig {
+// Payload of the "config-error" event (config.toml edited into an unusable state)
+export interface ConfigErrorEvent {
+  message: string;
+  validation?: ValidationReport;
+}
+
+interface RustProviderConfig extends GenerationParams {
   type: string;
   base_url: string;
   api_key: string;
   model: string;
   context_window?: number;
   api_version?: string;
+  retry?: RetryPolicy;
+  openai_api?: OpenAiApi;
+  deployment?: string;
+  ollama?: OllamaOptions;
+  thinking_budget?: number;
 }
 
 interface RustAppConfig {
+  version?: number;
   default: string;
+  fallback?: string[];
   providers: Record<string, RustProviderConfig>;
+  pricing?: Record<string, ModelPrice>;
+  network?: NetworkConfig;
+  active_profile?: string;
+  profiles?: Record<string, Profile>;
+}
+
+function pickParams(p: GenerationParams): GenerationParams {
+  return {
+    temperature: p.temperature,
+    max_tokens: p.max_tokens,
+    top_p: p.top_p,
+    stop_sequences: p.stop_sequences,
+  };
+}
+
+function toRustProvider(name: string, provider: ProviderConfig): RustProviderConfig {
+  const p = provider as ProviderConfig & { type?: string };
+  const providerType = p.provider_type ?? p.type;
+  if (!providerType) {
+    throw new Error(`Missing provider_type for provider "${name}"`);
+  }
+  return {
+    type: providerType,
+    base_url: p.base_url,
+    api_key: p.api_key,
+    model: p.model,
+    context_window: p.context_window,
+    api_version: p.api_version,
+    retry: p.retry,
+    openai_api: p.openai_api,
+    deployment: p.deployment,
+    ollama: p.ollama,
+    thinking_budget: p.thinking_budget,
+    ...pickParams(p),
+  };
 }
 
 function toRustConfig(config: AppConfig): RustAppConfig {
   const providers: Record<string, RustProviderConfig> = {};
   for (const [name, provider] of Object.entries(config.providers)) {
+    providers[name] = toRustProvider(name, provider);
+  }
+  return {
+    version: config.version,
+    default: config.default,
+    fallback: config.fallback,
+    providers,
+    pricing: config.pricing,
+    network: config.network,
+    active_profile: config.active_profile,
+    profiles: config.profiles,
+  };
+}
+
+// Map the Rust config (provider `type`) to the frontend shape (`provider_type`)
+function normalizeAppConfig(loadedConfig: AppConfig): AppConfig {
+  const normalizedProviders: Record<string, ProviderConfig> = {};
+  for (const [name, provider] of Object.entries(loadedConfig.providers)) {
     const p = provider as ProviderConfig & { type?: string };
-    const providerType = p.provider_type ?? p.type;
-    if (!providerType) {
-      throw new Error(`Missing provider_type for provider "${name}"`);
-    }
-    providers[name] = {
-      type: providerType,
+    const provider_type = p.provider_type ?? p.type ?? "openai";
+    normalizedProviders[name] = {
+      provider_type,
       base_url: p.base_url,
       api_key: p.api_key,
       model: p.model,
       context_window: p.context_window,
       api_version: p.api_version,
+      retry: p.retry,
+      openai_api: p.openai_api,
+      deployment: p.deployment,
+      ollama: p.ollama,
+      thinking_budget: p.thinking_budget,
+      ...pickParams(p),
     };
   }
-  return { default: config.default, providers };
+
+  return {
+    version: loadedConfig.version,
+    default: loadedConfig.default,
+    fallback: loadedConfig.fallback,
+    providers: normalizedProviders,
+    pricing: loadedConfig.pricing,
+    network: loadedConfig.network,
+    active_profile: loadedConfig.active_profile,
+    profiles: loadedConfig.profiles,
+  };
+}
+
+// Request config for a provider, with the active profile's overrides applied
+function toLlmConfig(appConfig: AppConfig, providerName: string): LlmConfig | null {
+  const provider = appConfig.providers[providerName];
+  if (!provider) return null;
+
+  const profile = appConfig.active_profile
+    ? appConfig.profiles?.[appConfig.active_profile]
+    : undefined;
+  const params = pickParams(provider);
+  if (profile) {
+    for (const [key, value] of Object.entries(pickParams(profile))) {
+      if (value !== undefined) {
+        (params as Record<string, unknown>)[key] = value;
+      }
+    }
+  }
+
+  return {
+    provider: providerName,
+    provider_type: provider.provider_type,
+    base_url: provider.base_url,
+    api_key: provider.api_key,
+    model: provider.model,
+    context_window: provider.context_window,
+    api_version: provider.api_version,
+    openai_api: provider.openai_api,
+    deployment: provider.deployment,
+    ollama: provider.ollama,
+    thinking_budget: provider.thinking_budget,
+    ...params,
+  };
 }
 
 // Provider type templates for creating new providers
@@ -43,6 +174,7 @@ export const PROVIDER_TYPE_TEMPLATES: Record<string, Partial<ProviderConfig>> =
     base_url: "https://api.openai.com/v1",
     model: "gpt-4o",
     context_window: 128000,
+    temperature: 0.3,
   },
   claude: {
     provider_type: "claude",
@@ -50,12 +182,28 @@ export const PROVIDER_TYPE_TEMPLATES: Record<string, Partial<ProviderConfig>> =
     model: "claude-sonnet-4-20250514",
     context_window: 200000,
     api_version: "2023-06-01",
+    max_tokens: 8192,
   },
   gemini: {
     provider_type: "gemini",
     base_url: "https://generativelanguage.googleapis.com",
     model: "gemini-1.5-flash",
     context_window: 1000000,
+    temperature: 0.3,
+  },
+  azure_openai: {
+    provider_type: "azure_openai",
+    base_url: "https://YOUR-RESOURCE.openai.azure.com",
+    model: "gpt-4o",
+    context_window: 128000,
+    api_version: "2024-10-21",
+    temperature: 0.3,
+  },
+  ollama: {
+    provider_type: "ollama",
+    base_url: "http://localhost:11434",
+    model: "llama3.2",
+    temperature: 0.3,
   },
 };
 
@@ -65,6 +213,19 @@ export function useConfig() {
   const [loading, setLoading] = useState(true);
   const [saving, setSaving] = useState(false);
   const [configPath, setConfigPath] = useState<string>("");
+  const [configError, setConfigError] = useState<ConfigErrorEvent | null>(null);
+  // Messages from loading config.toml, e.g. about migrating a legacy file
+  const [configNotices, setConfigNotices] = useState<string[]>([]);
+
+  const applyLoadedConfig = useCallback((loadedConfig: AppConfig) => {
+    const normalizedConfig = normalizeAppConfig(loadedConfig);
+    setAppConfig(normalizedConfig);
+
+    const activeConfig = toLlmConfig(normalizedConfig, normalizedConfig.default);
+    if (activeConfig) {
+      setConfig(activeConfig);
+    }
+  }, []);
 
   // Load config on mount
   useEffect(() => {
@@ -75,42 +236,9 @@ export function useConfig() {
           invoke<string>("get_config_file_path"),
         ]);
 
-        const normalizedProviders: Record<string, ProviderConfig> = {};
-        for (const [name, provider] of Object.entries(
-          (loadedConfig as AppConfig).providers,
-        )) {
-          const p = provider as ProviderConfig & { type?: string };
-          const provider_type = p.provider_type ?? p.type ?? "openai";
-          normalizedProviders[name] = {
-            provider_type,
-            base_url: p.base_url,
-            api_key: p.api_key,
-            model: p.model,
-            context_window: p.context_window,
-            api_version: p.api_version,
-          };
-        }
-
-        const normalizedConfig: AppConfig = {
-          default: loadedConfig.default,
-          providers: normalizedProviders,
-        };
-
-        setAppConfig(normalizedConfig);
+        applyLoadedConfig(loadedConfig);
         setConfigPath(path);
-
-        const activeProvider = normalizedConfig.providers[normalizedConfig.default];
-        if (activeProvider) {
-          setConfig({
-            provider: normalizedConfig.default,
-            provider_type: activeProvider.provider_type,
-            base_url: activeProvider.base_url,
-            api_key: activeProvider.api_key,
-            model: activeProvider.model,
-            context_window: activeProvider.context_window,
-            api_version: activeProvider.api_version,
-          });
-        }
+        setConfigNotices(await invoke<string[]>("take_config_notices"));
       } catch (e) {
         console.error("Failed to load config:", e);
       } finally {
@@ -119,7 +247,24 @@ export function useConfig() {
     };
 
     loadConfig();
-  }, []);
+  }, [applyLoadedConfig]);
+
+  // Follow external edits of config.toml
+  useEffect(() => {
+    const unlistenChanged = listen<AppConfig>("config-changed", (event) => {
+      setConfigError(null);
+      applyLoadedConfig(event.payload);
+    });
+    const unlistenError = listen<ConfigErrorEvent>("config-error", (event) => {
+      console.error("Config file error:", event.payload.message);
+      setConfigError(event.payload);
+    });
+
+    return () => {
+      unlistenChanged.then((fn) => fn());
+      unlistenError.then((fn) => fn());
+    };
+  }, [applyLoadedConfig]);
 
   // Save entire app config
   const saveAppConfig = useCallback(async (newAppConfig: AppConfig) => {
@@ -128,19 +273,12 @@ export function useConfig() {
       // Convert to Rust-compatible format (provider_type -> type)
       await invoke("save_toml_config", { config: toRustConfig(newAppConfig) });
       setAppConfig(newAppConfig);
+      setConfigError(null);
       
       // Update active config
-      const activeProvider = newAppConfig.providers[newAppConfig.default];
-      if (activeProvider) {
-        setConfig({
-          provider: newAppConfig.default,
-          provider_type: activeProvider.provider_type,
-          base_url: activeProvider.base_url,
-          api_key: activeProvider.api_key,
-          model: activeProvider.model,
-          context_window: activeProvider.context_window,
-          api_version: activeProvider.api_version,
-        });
+      const activeConfig = toLlmConfig(newAppConfig, newAppConfig.default);
+      if (activeConfig) {
+        setConfig(activeConfig);
       }
     } catch (e) {
       console.error("Failed to save config:", e);
@@ -161,17 +299,9 @@ export function useConfig() {
       const newAppConfig = { ...appConfig, default: providerName };
       setAppConfig(newAppConfig);
       
-      const activeProvider = appConfig.providers[providerName];
-      if (activeProvider) {
-        setConfig({
-          provider: providerName,
-          provider_type: activeProvider.provider_type,
-          base_url: activeProvider.base_url,
-          api_key: activeProvider.api_key,
-          model: activeProvider.model,
-          context_window: activeProvider.context_window,
-          api_version: activeProvider.api_version,
-        });
+      const activeConfig = toLlmConfig(newAppConfig, providerName);
+      if (activeConfig) {
+        setConfig(activeConfig);
       }
     } catch (e) {
       console.error("Failed to set default provider:", e);
@@ -228,6 +358,7 @@ export function useConfig() {
     }
     
     const newAppConfig: AppConfig = {
+      ...appConfig,
       default: newDefault,
       providers: newProviders,
     };
@@ -235,14 +366,91 @@ export function useConfig() {
     await saveAppConfig(newAppConfig);
   }, [appConfig, saveAppConfig]);
 
-  // Test connection with real HTTP request
-  const testConnection = useCallback(async (provider: ProviderConfig) => {
-    await invoke("test_llm_connection", {
-      providerType: provider.provider_type,
-      baseUrl: provider.base_url,
-      apiKey: provider.api_key,
-      model: provider.model,
-      apiVersion: provider.api_version,
+  // Check a config for field-level errors and warnings without saving it
+  const validateConfig = useCallback(async (newAppConfig: AppConfig) => {
+    return invoke<ValidationReport>("validate_config", { config: toRustConfig(newAppConfig) });
+  }, []);
+
+  // Saved copies of config.toml, newest first
+  const listConfigBackups = useCallback(async () => {
+    return invoke<ConfigBackup[]>("list_config_backups");
+  }, []);
+
+  // Replace config.toml with a backup (the current file is backed up first)
+  const restoreConfigBackup = useCallback(async (name: string) => {
+    const restored = await invoke<AppConfig>("restore_config_backup", { name });
+    setConfigError(null);
+    applyLoadedConfig(restored);
+  }, [applyLoadedConfig]);
+
+  // Write providers to a shareable file in the downloads folder, returning its path
+  const exportConfig = useCallback(async (includeKeys = false) => {
+    return invoke<string>("export_config", { includeKeys });
+  }, []);
+
+  // Merge providers from an exported file (or a config.toml) into the config
+  const importConfig = useCallback(async (content: string, onConflict: ConflictStrategy) => {
+    setSaving(true);
+    try {
+      const result = await invoke<ImportResult>("import_config", { content, onConflict });
+      setConfigError(null);
+      applyLoadedConfig(result.config);
+      return result;
+    } finally {
+      setSaving(false);
+    }
+  }, [applyLoadedConfig]);
+
+  // Named profiles, sorted by name
+  const listProfiles = useCallback(async () => {
+    return invoke<ProfileSummary[]>("list_profiles");
+  }, []);
+
+  // Save the current provider choices as a new profile and switch to it
+  const createProfile = useCallback(async (name: string, defaultProvider?: string) => {
+    setSaving(true);
+    try {
+      const updated = await invoke<AppConfig>("create_profile", { name, default: defaultProvider });
+      applyLoadedConfig(updated);
+    } finally {
+      setSaving(false);
+    }
+  }, [applyLoadedConfig]);
+
+  // Activate a profile, switching to its default provider and parameters
+  const switchProfile = useCallback(async (name: string) => {
+    setSaving(true);
+    try {
+      const updated = await invoke<AppConfig>("switch_profile", { name });
+      applyLoadedConfig(updated);
+    } finally {
+      setSaving(false);
+    }
+  }, [applyLoadedConfig]);
+
+  // Copy a profile under a new name; the active profile does not change
+  const duplicateProfile = useCallback(async (source: string, name: string) => {
+    setSaving(true);
+    try {
+      const updated = await invoke<AppConfig>("duplicate_profile", { source, name });
+      applyLoadedConfig(updated);
+    } finally {
+      setSaving(false);
+    }
+  }, [applyLoadedConfig]);
+
+  // Models offered by a saved provider; cached by the backend unless refresh is set
+  const listModels = useCallback(async (providerName: string, refresh = false) => {
+    return invoke<ModelInfo[]>("list_models", { providerName, refresh });
+  }, []);
+
+  // Check a provider step by step; failures are described in the report.
+  // `name` only labels errors and may be empty for an unsaved provider.
+  const testConnection = useCallback(async (name: string, provider: ProviderConfig) => {
+    const providerName = name.trim() || "(unsaved)";
+    return invoke<DiagnosticReport>("test_llm_connection", {
+      providerName,
+      provider: toRustProvider(providerName, provider),
     });
   }, []);
 
@@ -252,11 +460,23 @@ export function useConfig() {
     loading,
     saving,
     configPath,
+    configError,
+    configNotices,
     saveAppConfig,
     setDefaultProvider,
     addProvider,
     updateProvider,
     deleteProvider,
+    validateConfig,
+    listConfigBackups,
+    restoreConfigBackup,
+    exportConfig,
+    importConfig,
+    listProfiles,
+    createProfile,
+    switchProfile,
+    duplicateProfile,
+    listModels,
     testConnection,
     providerTypeTemplates: PROVIDER_TYPE_TEMPLATES,
   };
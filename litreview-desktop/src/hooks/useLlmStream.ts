@@ -2,54 +2,311 @@ import { useState, useCallback, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
+export interface TokenUsage {
+  input_tokens: number;
+  output_tokens: number;
+}
+
+// "length" means the output was truncated by the token limit
+export type FinishReason = "stop" | "length" | "content_filter" | "tool_use" | "other";
+
+// Informational event: a failed attempt is about to be retried
+export interface RetryNotice {
+  attempt: number;
+  max_attempts: number;
+  delay_ms: number;
+  reason: string;
+}
+
+// Informational event: the provider failed and the next fallback is being tried
+export interface FallbackNotice {
+  from: string;
+  to: string;
+  reason: string;
+}
+
+// "reasoning" deltas carry the model's thinking, shown apart from the answer
+export type DeltaKind = "text" | "reasoning";
+
 export interface LlmStreamEvent {
   stream_id: string;
   delta: string;
+  kind: DeltaKind;
   done: boolean;
-  error?: string;
+  cancelled: boolean;
+  error?: AppError;
+  usage?: TokenUsage;
+  finish_reason?: FinishReason;
+  retry?: RetryNotice;
+  fallback?: FallbackNotice;
+  provider?: string;  // Provider that served the response, on the final event
+}
+
+// A single turn of a multi-turn conversation
+export interface ChatMessage {
+  role: "system" | "user" | "assistant";
+  content: string;
+}
+
+// Optional sampling parameters; unset fields use the provider's defaults
+export interface GenerationParams {
+  temperature?: number;
+  max_tokens?: number;
+  top_p?: number;
+  stop_sequences?: string[];
+}
+
+// Retry settings for transient failures (429/5xx, network errors)
+export interface RetryPolicy {
+  max_attempts?: number;
+  initial_backoff_ms?: number;
+  max_backoff_ms?: number;
 }
 
 // Provider configuration for TOML file
-export interface ProviderConfig {
-  provider_type: string;  // "openai" | "claude" | "gemini"
+// Endpoint of an "openai" provider: chat completions or the Responses API
+export type OpenAiApi = "chat_completions" | "responses";
+
+// Settings for the native "ollama" provider type
+export interface OllamaOptions {
+  num_ctx?: number;     // Context length in tokens
+  keep_alive?: string;  // How long the model stays loaded, e.g. "10m"
+  raw?: boolean;        // Skip the model's prompt template
+}
+
+export interface ProviderConfig extends GenerationParams {
+  provider_type: string;  // "openai" | "claude" | "gemini" | "ollama" | "azure_openai"
   base_url: string;
   api_key: string;
   model: string;
   context_window?: number;
-  api_version?: string;  // Required for Claude
+  api_version?: string;  // Required for Claude and Azure OpenAI
+  retry?: RetryPolicy;
+  openai_api?: OpenAiApi;
+  deployment?: string;   // Azure OpenAI deployment name, defaults to model
+  ollama?: OllamaOptions;
+  thinking_budget?: number;  // Extended thinking tokens, Claude and Gemini only
+}
+
+// Model price in USD per million tokens
+export interface ModelPrice {
+  input: number;
+  output: number;
+}
+
+export type UsageGrouping = "day" | "provider" | "model" | "feature";
+
+// Entry returned by get_usage_stats; durations in milliseconds
+export interface UsageBucket {
+  key: string;
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost: number;  // USD, excluding unpriced_requests
+  unpriced_requests: number;
+  duration_ms: number;
+}
+
+// HTTP client settings ([network] in config.toml)
+export interface NetworkConfig {
+  proxy?: string;
+  no_proxy?: string;  // Comma-separated hosts that bypass the proxy
+  connect_timeout_secs?: number;
+  read_timeout_secs?: number;
+  ca_bundle?: string;  // Path to a PEM file with extra root certificates
+  insecure?: boolean;  // Accept invalid certificates (local servers only)
 }
 
 // Full app configuration
 export interface AppConfig {
+  version?: number;  // Schema version, set by the backend on save
   default: string;  // Name of the active provider
+  fallback?: string[];  // Providers tried in order when the active one fails
   providers: Record<string, ProviderConfig>;
+  pricing?: Record<string, ModelPrice>;  // Keyed by model id
+  network?: NetworkConfig;
+  active_profile?: string;  // Profile whose choices `default` and `fallback` hold
+  profiles?: Record<string, Profile>;
+}
+
+// Named provider choices; its params override those of every provider
+export interface Profile extends GenerationParams {
+  default: string;
+  fallback?: string[];
+}
+
+// Entry returned by list_profiles
+export interface ProfileSummary {
+  name: string;
+  default: string;
+  active: boolean;
+}
+
+// Saved copy of config.toml (see restore_config_backup)
+export interface ConfigBackup {
+  name: string;
+  created: string;  // RFC 3339 timestamp
+  size: number;
+}
+
+// Model reported by a provider's model list endpoint (see list_models)
+export interface ModelInfo {
+  id: string;
+  display_name?: string;
+  context_window?: number;
+  max_output_tokens?: number;
+  capabilities?: string[];  // Provider-specific, e.g. Gemini "generateContent"
+}
+
+// Classified cause of a failed provider request
+export type ErrorKind =
+  | "auth"
+  | "model_not_found"
+  | "not_found"
+  | "quota"
+  | "network"
+  | "timeout"
+  | "proxy"
+  | "tls"
+  | "bad_request"
+  | "server"
+  | "invalid_response"
+  | "config"
+  | "invalid_input"
+  | "storage"
+  | "unknown";
+
+// Error returned by every command and carried in LlmStreamEvent.error
+export interface AppError {
+  kind: ErrorKind;
+  code: string;      // Provider error code such as "insufficient_quota", else e.g. "http_404"
+  message: string;   // English detail from the backend
+  provider?: string;
+  http_status?: number;
+  retryable: boolean;
+  raw?: string;      // Truncated response body or error chain
+}
+
+// User-facing text for each error kind
+export const ERROR_KIND_MESSAGES: Record<ErrorKind, string> = {
+  auth: "API Key 无效、已过期或无权访问该模型",
+  model_not_found: "模型不存在，请检查模型名称",
+  not_found: "接口地址不存在，请检查 Base URL",
+  quota: "请求过于频繁或额度已用完",
+  network: "无法连接到服务器，请检查网络和 Base URL",
+  timeout: "请求超时",
+  proxy: "代理不可用或拒绝了请求，请检查代理设置",
+  tls: "TLS 证书校验失败，可配置 CA 证书",
+  bad_request: "服务器拒绝了请求参数",
+  server: "服务器内部错误，请稍后重试",
+  invalid_response: "无法解析服务器的响应",
+  config: "配置有误",
+  invalid_input: "输入无效",
+  storage: "读写文件失败",
+  unknown: "未知错误",
+};
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === "object" && e !== null && "kind" in e && "message" in e;
+}
+
+// Localized message for anything a command or stream event failed with
+export function describeError(e: unknown): string {
+  if (isAppError(e)) {
+    return `${ERROR_KIND_MESSAGES[e.kind] ?? ERROR_KIND_MESSAGES.unknown}（${e.message}）`;
+  }
+  return e instanceof Error ? e.message : String(e);
+}
+
+// Result of test_llm_connection; timings in milliseconds, unset when skipped
+export interface DiagnosticReport {
+  ok: boolean;
+  proxy?: string;  // DNS/connect timings are the proxy's when set
+  dns_ms: number | null;
+  addresses: string[];
+  connect_ms: number | null;
+  tls_ms: number | null;
+  status: number | null;
+  response_ms: number | null;
+  streaming: boolean | null;
+  first_token_ms: number | null;
+  stream_ms: number | null;
+  error?: AppError & {
+    step: "url" | "dns" | "connect" | "tls" | "request" | "stream";
+  };
+  warnings: string[];
+}
+
+// How import_config treats a provider whose name is already in use
+export type ConflictStrategy = "skip" | "replace" | "rename";
+
+// Result of import_config
+export interface ImportResult {
+  added: string[];
+  replaced: string[];
+  renamed: { from: string; to: string }[];
+  skipped: string[];
+  config: AppConfig;
+}
+
+// Problem with one config field, e.g. field "providers.openai.base_url"
+export interface FieldIssue {
+  field: string;
+  message: string;
+}
+
+// Result of validate_config; errors block saving, warnings do not
+export interface ValidationReport {
+  errors: FieldIssue[];
+  warnings: FieldIssue[];
 }
 
 // Active LLM configuration (with provider name)
-export interface LlmConfig {
+export interface LlmConfig extends GenerationParams {
   provider: string;       // Provider name (key in providers map)
-  provider_type: string;  // "openai" | "claude" | "gemini"
+  provider_type: string;  // "openai" | "claude" | "gemini" | "ollama" | "azure_openai"
   base_url: string;
   api_key: string;
   model: string;
   context_window?: number;
   api_version?: string;
+  openai_api?: OpenAiApi;
+  deployment?: string;
+  ollama?: OllamaOptions;
+  thinking_budget?: number;
+}
+
+export interface StreamOptions {
+  systemPrompt?: string;
+  // Earlier turns of the conversation
+  history?: ChatMessage[];
+  // Feature recorded in the usage ledger, e.g. "review" or "polish"
+  feature?: string;
 }
 
 export interface UseLlmStreamReturn {
   content: string;
+  reasoning: string;  // Thinking streamed before the answer, if the model sends it
   loading: boolean;
   error: string | null;
+  errorDetails: AppError | null;  // Structured form of `error` when the backend sent one
   streamId: string | null;
-  startStream: (prompt: string, config: LlmConfig, systemPrompt?: string) => Promise<void>;
+  usage: TokenUsage | null;
+  finishReason: FinishReason | null;
+  startStream: (prompt: string, config: LlmConfig, options?: StreamOptions) => Promise<void>;
+  cancelStream: () => Promise<void>;
   reset: () => void;
 }
 
 export function useLlmStream(): UseLlmStreamReturn {
   const [content, setContent] = useState("");
+  const [reasoning, setReasoning] = useState("");
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [errorDetails, setErrorDetails] = useState<AppError | null>(null);
   const [streamId, setStreamId] = useState<string | null>(null);
+  const [usage, setUsage] = useState<TokenUsage | null>(null);
+  const [finishReason, setFinishReason] = useState<FinishReason | null>(null);
   const activeStreamId = useRef<string | null>(null);
   const unlistenRef = useRef<UnlistenFn | null>(null);
 
@@ -61,22 +318,29 @@ export function useLlmStream(): UseLlmStreamReturn {
       unlistenRef.current = await listen<LlmStreamEvent>("llm-stream", (event) => {
         if (!mounted) return;
         
-        const { stream_id, delta, done, error } = event.payload;
+        const { stream_id, delta, kind, done, error, usage, finish_reason } = event.payload;
         
         // Only process events for the active stream
         if (stream_id !== activeStreamId.current) return;
 
         if (error) {
-          setError(error);
+          setError(describeError(error));
+          setErrorDetails(error);
           setLoading(false);
           return;
         }
 
         if (delta) {
-          setContent((prev) => prev + delta);
+          if (kind === "reasoning") {
+            setReasoning((prev) => prev + delta);
+          } else {
+            setContent((prev) => prev + delta);
+          }
         }
 
         if (done) {
+          setUsage(usage ?? null);
+          setFinishReason(finish_reason ?? null);
           setLoading(false);
         }
       });
@@ -92,36 +356,69 @@ export function useLlmStream(): UseLlmStreamReturn {
     };
   }, []);
 
-  const startStream = useCallback(async (prompt: string, config: LlmConfig, systemPrompt?: string) => {
+  const startStream = useCallback(async (prompt: string, config: LlmConfig, options: StreamOptions = {}) => {
     // Reset state
     setContent("");
+    setReasoning("");
     setError(null);
+    setErrorDetails(null);
+    setUsage(null);
+    setFinishReason(null);
     setLoading(true);
 
     try {
       const newStreamId = await invoke<string>("start_llm_stream", {
         config: {
+          provider: config.provider,
+          feature: options.feature ?? null,
           provider_type: config.provider_type,
           base_url: config.base_url,
           api_key: config.api_key,
           model: config.model,
           prompt,
           api_version: config.api_version,
-          system_prompt: systemPrompt || null,
+          system_prompt: options.systemPrompt || null,
+          messages: options.history ?? [],
+          temperature: config.temperature,
+          max_tokens: config.max_tokens,
+          top_p: config.top_p,
+          stop_sequences: config.stop_sequences,
+          openai_api: config.openai_api,
+          deployment: config.deployment,
+          ollama: config.ollama,
+          thinking_budget: config.thinking_budget,
         },
       });
 
       activeStreamId.current = newStreamId;
       setStreamId(newStreamId);
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(describeError(e));
+      setErrorDetails(isAppError(e) ? e : null);
+      setLoading(false);
+    }
+  }, []);
+
+  const cancelStream = useCallback(async () => {
+    const id = activeStreamId.current;
+    if (!id) return;
+
+    try {
+      await invoke<boolean>("cancel_llm_stream", { streamId: id });
+    } catch (e) {
+      setError(describeError(e));
+    } finally {
       setLoading(false);
     }
   }, []);
 
   const reset = useCallback(() => {
     setContent("");
+    setReasoning("");
     setError(null);
+    setErrorDetails(null);
+    setUsage(null);
+    setFinishReason(null);
     setLoading(false);
     setStreamId(null);
     activeStreamId.current = null;
@@ -129,10 +426,15 @@ export function useLlmStream(): UseLlmStreamReturn {
 
   return {
     content,
+    reasoning,
     loading,
     error,
+    errorDetails,
     streamId,
+    usage,
+    finishReason,
     startStream,
+    cancelStream,
     reset,
   };
 }